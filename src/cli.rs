@@ -0,0 +1,171 @@
+use std::env;
+use std::path::PathBuf;
+
+const USAGE: &str = "\
+usage: inori [options] [command]
+
+Without a command, starts the interface.
+
+commands:
+  play                 start or resume playback
+  pause                pause playback
+  toggle               toggle between playing and paused
+  next                 play the next song in the queue
+  prev                 play the previous song in the queue
+  seek [+|-]<seconds>  seek within the current song (default: seek_seconds)
+  clear                clear the queue
+  add <query>          add the best global search match to the queue
+  status [--follow] [--format json|<template>]
+                       print the current song and playback state. With
+                       --follow, print a line whenever it changes.
+                       Templates may use {title} {artist} {album}
+                       {state} {elapsed} {duration} {progress} {repeat}
+                       {random} {single} {consume}
+
+options:
+  -h, --host <host>    server to connect to, overriding MPD_HOST
+  -p, --port <port>    port to connect to, overriding MPD_PORT
+  -c, --config <path>  config file to read, overriding INORI_CONFIG
+  -V, --version        print the version and exit
+      --help           print this help and exit
+";
+
+#[derive(Default)]
+pub struct Args {
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub config: Option<PathBuf>,
+    pub command: Option<Command>,
+}
+
+pub enum Command {
+    Play,
+    Pause,
+    Toggle,
+    Next,
+    Prev,
+    Seek(Option<i64>),
+    Clear,
+    Add(String),
+    Status(StatusOpts),
+}
+
+#[derive(Default)]
+pub struct StatusOpts {
+    pub follow: bool,
+    pub format: Option<StatusFormat>,
+}
+
+pub enum StatusFormat {
+    Json,
+    Template(String),
+}
+
+pub enum Parsed {
+    Run(Args),
+    Help,
+    Version,
+}
+
+pub fn usage() -> &'static str {
+    USAGE
+}
+
+pub fn version() -> String {
+    format!("inori {}", env!("CARGO_PKG_VERSION"))
+}
+
+// accepts both `--opt value` and `--opt=value`
+pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Parsed, String> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .map(str::to_string)
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match flag.as_str() {
+            "--help" => return Ok(Parsed::Help),
+            "-V" | "--version" => return Ok(Parsed::Version),
+            "-h" | "--host" => parsed.host = Some(value("--host")?),
+            "-p" | "--port" => parsed.port = Some(value("--port")?),
+            "-c" | "--config" => {
+                parsed.config = Some(PathBuf::from(value("--config")?))
+            }
+            other if other.starts_with('-') => {
+                return Err(format!("unknown argument {}", other))
+            }
+            _ => {
+                parsed.command = Some(parse_command(&arg, args.collect())?);
+                break;
+            }
+        }
+    }
+    Ok(Parsed::Run(parsed))
+}
+
+// everything after the command name belongs to the command
+fn parse_command(name: &str, rest: Vec<String>) -> Result<Command, String> {
+    let no_args = |cmd| match rest.is_empty() {
+        true => Ok(cmd),
+        false => Err(format!("{} takes no arguments", name)),
+    };
+    match name {
+        "play" => no_args(Command::Play),
+        "pause" => no_args(Command::Pause),
+        "toggle" => no_args(Command::Toggle),
+        "next" => no_args(Command::Next),
+        "prev" => no_args(Command::Prev),
+        "clear" => no_args(Command::Clear),
+        "status" => parse_status(rest).map(Command::Status),
+        "seek" => match rest.as_slice() {
+            [] => Ok(Command::Seek(None)),
+            [secs] => secs
+                .trim_start_matches('+')
+                .parse()
+                .map(|s| Command::Seek(Some(s)))
+                .map_err(|_| format!("invalid seek offset {}", secs)),
+            _ => Err("seek takes at most one argument".to_string()),
+        },
+        "add" if rest.is_empty() => Err("add needs a query".to_string()),
+        "add" => Ok(Command::Add(rest.join(" "))),
+        other => Err(format!("unknown command {}", other)),
+    }
+}
+
+// --config, then INORI_CONFIG. None means the default location.
+pub fn config_path(args: &Args) -> Option<PathBuf> {
+    args.config
+        .clone()
+        .or_else(|| env::var_os("INORI_CONFIG").map(PathBuf::from))
+}
+
+fn parse_status(rest: Vec<String>) -> Result<StatusOpts, String> {
+    let mut opts = StatusOpts::default();
+    let mut rest = rest.into_iter();
+    while let Some(arg) = rest.next() {
+        let format = match arg.split_once('=') {
+            Some(("--format", f)) => Some(f.to_string()),
+            _ if arg == "--format" => {
+                Some(rest.next().ok_or("--format needs a value".to_string())?)
+            }
+            _ => None,
+        };
+        match (arg.as_str(), format) {
+            (_, Some(f)) if f == "json" => {
+                opts.format = Some(StatusFormat::Json)
+            }
+            (_, Some(f)) => opts.format = Some(StatusFormat::Template(f)),
+            ("-f" | "--follow", None) => opts.follow = true,
+            (other, None) => {
+                return Err(format!("unknown status argument {}", other))
+            }
+        }
+    }
+    Ok(opts)
+}
@@ -1,127 +1,312 @@
 extern crate dirs;
+use crate::connection::Server;
 use crate::model::*;
 use crate::view::Theme;
 use ratatui::style::Style;
+use std::fmt;
 use std::fs;
 use std::path::PathBuf;
+use std::time::SystemTime;
 use toml::Table;
 use toml::Value;
+use toml_edit::ImDocument;
 pub mod keybind;
 use keybind::{get_message, KeybindMap};
 
 pub struct Config {
+    // where the config was read from, so that it can be read again
+    pub path: Option<PathBuf>,
     pub keybindings: KeybindMap,
     pub theme: Theme,
     pub seek_seconds: i64,
+    // how many submitted searches to remember
+    pub history_size: usize,
+    pub smart_case: bool,
+    pub password: Option<String>,
+    pub servers: Vec<ServerProfile>,
+    pub default_server: Option<String>,
+    pub watch_config: bool,
+    // modification time of the file when it was read
+    pub modified: Option<SystemTime>,
+    // broken entries are skipped, keeping their defaults
+    pub errors: Vec<ConfigError>,
+    // kept around to find the line of an error
+    source: Option<ImDocument<String>>,
+}
+
+pub struct ServerProfile {
+    pub name: String,
+    pub host: Option<String>,
+    pub port: Option<String>,
+    pub password: Option<String>,
+    pub keybindings: Option<Table>,
+    pub theme: Option<Table>,
+}
+
+pub struct ConfigError {
+    pub path: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "{}", self.message)
+    }
 }
 
 impl Config {
     pub fn default() -> Self {
         Config {
+            path: None,
             keybindings: KeybindMap::default(),
             theme: Theme::new(),
             seek_seconds: 5,
+            history_size: 100,
+            smart_case: false,
+            password: None,
+            servers: Vec::new(),
+            default_server: None,
+            watch_config: false,
+            modified: None,
+            errors: Vec::new(),
+            source: None,
         }
     }
-    pub fn try_read_config(mut self) -> Self {
-        let path = dirs::config_dir().map(|mut p| {
-            p.push(PathBuf::from_iter(["inori", "config.toml"]));
-            p
+    pub fn try_read_config(mut self, path: Option<PathBuf>) -> Self {
+        let path = path.or_else(|| {
+            dirs::config_dir().map(|mut p| {
+                p.push(PathBuf::from_iter(["inori", "config.toml"]));
+                p
+            })
         });
-        if let Some(Ok(contents)) = path.map(fs::read_to_string) {
-            let toml = contents.parse::<Table>().expect("failed to parse toml");
-            for (key, value) in toml {
-                match (key.as_str(), value) {
-                    ("keybindings", Value::Table(t)) => self.read_keybinds(t),
-                    ("seek_seconds", Value::Integer(k)) if k > 0 => {
-                        self.seek_seconds = k
-                    }
-                    ("theme", Value::Table(t)) => self.read_theme(t),
-                    ("dvorak_keybindings", Value::Boolean(true)) => {
+        self.path = path.clone();
+        let Some(path) = path else {
+            return self;
+        };
+        self.modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let Ok(contents) = fs::read_to_string(path) else {
+            return self;
+        };
+        let toml = match contents.parse::<Table>() {
+            Ok(toml) => toml,
+            Err(e) => {
+                self.errors.push(ConfigError {
+                    path: String::new(),
+                    line: e.span().map(|s| line_at(&contents, s.start)),
+                    message: e.message().trim().replace('\n', " "),
+                });
+                return self;
+            }
+        };
+        self.source = ImDocument::parse(contents).ok();
+        for (key, value) in toml {
+            match (key.as_str(), value) {
+                ("keybindings", Value::Table(t)) => {
+                    self.read_keybinds(t, &["keybindings"])
+                }
+                ("seek_seconds", Value::Integer(k)) if k > 0 => {
+                    self.seek_seconds = k
+                }
+                ("history_size", Value::Integer(k)) if k >= 0 => {
+                    self.history_size = k as usize
+                }
+                ("theme", Value::Table(t)) => self.read_theme(t, &["theme"]),
+                ("password", Value::String(s)) => self.password = Some(s),
+                ("servers", Value::Table(t)) => self.read_servers(t),
+                ("default_server", Value::String(s)) => {
+                    self.default_server = Some(s)
+                }
+                ("watch_config", Value::Boolean(b)) => self.watch_config = b,
+                ("smart_case", Value::Boolean(b)) => self.smart_case = b,
+                ("dvorak_keybindings", Value::Boolean(b)) => {
+                    if b {
                         self.keybindings = self.keybindings.with_dvorak_style();
                     }
-                    ("qwerty_keybindings", Value::Boolean(true)) => {
+                }
+                ("qwerty_keybindings", Value::Boolean(b)) => {
+                    if b {
                         self.keybindings = self.keybindings.with_qwerty_style();
                     }
-                    (_k, _v) => panic!("unknown key {} or value {}", _k, _v),
                 }
+                (
+                    "keybindings" | "seek_seconds" | "history_size" | "theme"
+                    | "password" | "servers" | "default_server"
+                    | "watch_config" | "smart_case" | "dvorak_keybindings"
+                    | "qwerty_keybindings",
+                    v,
+                ) => self.error(&[&key], format!("invalid value {}", v)),
+                (k, _) => self.error(&[k], "unknown key"),
             }
         }
+        if let Some(name) = self.default_server.clone() {
+            if !self.servers.iter().any(|p| p.name == name) {
+                self.error(&["default_server"], "no such server");
+            }
+        }
+        self.errors.sort_by_key(|e| e.line);
         self
     }
-    pub fn read_keybinds(&mut self, t: Table) {
+    // false if the file couldn't be read or isn't valid toml at all
+    pub fn loaded(&self) -> bool {
+        self.source.is_some()
+    }
+    // true if the file changed on disk since it was read
+    pub fn is_stale(&self) -> bool {
+        self.path
+            .as_ref()
+            .and_then(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+            .is_some_and(|m| Some(m) != self.modified)
+    }
+    fn error(&mut self, path: &[&str], message: impl Into<String>) {
+        self.errors.push(ConfigError {
+            path: path.join("."),
+            line: self.source.as_ref().and_then(|doc| line_of(doc, path)),
+            message: message.into(),
+        });
+    }
+    pub fn read_keybinds(&mut self, t: Table, prefix: &[&str]) {
         for (key, value) in t {
+            let path = [prefix, &[key.as_str()]].concat();
             match (get_message(&key), value) {
                 (Some(m), Value::String(s)) => {
-                    let keybinds = keybind::parse_keybind(s)
-                        .unwrap_or_else(|_| panic!("Couldn't parse keybinds"));
-                    self.keybindings.insert(m.clone(), &keybinds);
+                    match keybind::parse_keybind(s) {
+                        Ok(keybinds) => self.keybindings.insert(m, &keybinds),
+                        Err(e) => self.error(&path, e.to_string()),
+                    }
                 }
-                (Some(_), other) => panic!(
-                    "keybind {} for command {} must be a string",
-                    other, key
+                (Some(_), other) => self.error(
+                    &path,
+                    format!("keybind must be a string, not {}", other),
                 ),
-                (None, _) => panic!("message {} does not exist", key),
+                (None, _) => self.error(&path, "no such command"),
             }
         }
     }
-    pub fn read_theme(&mut self, t: Table) {
-        for (key, value) in t {
-            match (key.as_str(), value) {
-                ("item_highlight_active", Value::Table(t)) => {
-                    self.theme.item_highlight_active = deserialize_style(t);
-                }
-                ("item_highlight_inactive", Value::Table(t)) => {
-                    self.theme.item_highlight_inactive = deserialize_style(t);
-                }
-                ("block_active", Value::Table(t)) => {
-                    self.theme.block_active = deserialize_style(t);
-                }
-                ("status_artist", Value::Table(t)) => {
-                    self.theme.status_artist = deserialize_style(t);
-                }
-                ("status_album", Value::Table(t)) => {
-                    self.theme.status_album = deserialize_style(t);
-                }
-                ("status_title", Value::Table(t)) => {
-                    self.theme.status_title = deserialize_style(t);
-                }
-                ("artist_sort", Value::Table(t)) => {
-                    self.theme.artist_sort = deserialize_style(t);
-                }
-                ("album", Value::Table(t)) => {
-                    self.theme.album = deserialize_style(t);
-                }
-                ("playing", Value::Table(t)) => {
-                    self.theme.playing = deserialize_style(t);
-                }
-                ("paused", Value::Table(t)) => {
-                    self.theme.paused = deserialize_style(t);
-                }
-                ("stopped", Value::Table(t)) => {
-                    self.theme.stopped = deserialize_style(t);
-                }
-                ("slash_span", Value::Table(t)) => {
-                    self.theme.slash_span = deserialize_style(t);
+    pub fn read_servers(&mut self, t: Table) {
+        for (name, value) in t {
+            let Value::Table(t) = value else {
+                self.error(&["servers", &name], "server must be a table");
+                continue;
+            };
+            let mut profile = ServerProfile {
+                name,
+                host: None,
+                port: None,
+                password: None,
+                keybindings: None,
+                theme: None,
+            };
+            for (key, value) in t {
+                match (key.as_str(), value) {
+                    ("host", Value::String(s)) => profile.host = Some(s),
+                    ("port", Value::Integer(k)) => {
+                        profile.port = Some(k.to_string())
+                    }
+                    ("port", Value::String(s)) => profile.port = Some(s),
+                    ("password", Value::String(s)) => {
+                        profile.password = Some(s)
+                    }
+                    ("keybindings", Value::Table(t)) => {
+                        profile.keybindings = Some(t)
+                    }
+                    ("theme", Value::Table(t)) => profile.theme = Some(t),
+                    (
+                        k @ ("host" | "port" | "password" | "keybindings"
+                        | "theme"),
+                        v,
+                    ) => self.error(
+                        &["servers", &profile.name, k],
+                        format!("invalid value {}", v),
+                    ),
+                    (k, _) => self
+                        .error(&["servers", &profile.name, k], "unknown key"),
                 }
-                ("search_query_active", Value::Table(t)) => {
-                    self.theme.search_query_active = deserialize_style(t);
+            }
+            self.servers.push(profile);
+        }
+    }
+    pub fn server(&self, name: &str) -> Option<Server> {
+        self.servers
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| Server {
+                name: Some(p.name.clone()),
+                host: p.host.clone(),
+                port: p.port.clone().unwrap_or_else(|| "6600".to_string()),
+                password: p.password.clone().or_else(|| self.password.clone()),
+            })
+    }
+    // keybindings and theme of a profile are applied on top of the
+    // top-level ones.
+    pub fn with_profile(mut self, name: &str) -> Self {
+        let overrides = self
+            .servers
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| (p.keybindings.clone(), p.theme.clone()));
+        if let Some((keybindings, theme)) = overrides {
+            if let Some(t) = keybindings {
+                self.read_keybinds(t, &["servers", name, "keybindings"]);
+            }
+            if let Some(t) = theme {
+                self.read_theme(t, &["servers", name, "theme"]);
+            }
+        }
+        self
+    }
+    pub fn read_theme(&mut self, t: Table, prefix: &[&str]) {
+        for (key, value) in t {
+            let path = [prefix, &[key.as_str()]].concat();
+            let res = match (self.theme.style_mut(&key), value) {
+                (Some(style), Value::Table(t)) => {
+                    deserialize_style(t).map(|s| *style = s)
                 }
-                ("search_query_inactive", Value::Table(t)) => {
-                    self.theme.search_query_inactive = deserialize_style(t);
+                (Some(_), other) => {
+                    Err(format!("style must be a table, not {}", other))
                 }
-                (other, _) => panic!("theme option {} not found", other),
+                (None, _) => Err("no such theme option".to_string()),
+            };
+            if let Err(e) = res {
+                self.error(&path, e);
             }
         }
     }
 }
 
-pub fn deserialize_style(mut t: Table) -> Style {
+pub fn deserialize_style(mut t: Table) -> Result<Style, String> {
     if !t.contains_key("add_modifier") {
         t.insert("add_modifier".into(), Value::String("".into()));
     }
     if !t.contains_key("sub_modifier") {
         t.insert("sub_modifier".into(), Value::String("".into()));
     }
-    t.try_into().expect("Style parse failure")
+    t.try_into()
+        .map_err(|e: toml::de::Error| e.message().trim().to_string())
+}
+
+fn line_at(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+// the line of the deepest part of `path` that is still in the document
+fn line_of(doc: &ImDocument<String>, path: &[&str]) -> Option<usize> {
+    let mut item = doc.as_item();
+    let mut span = None;
+    for key in path {
+        let Some((k, v)) =
+            item.as_table_like().and_then(|t| t.get_key_value(key))
+        else {
+            break;
+        };
+        span = k.span().or_else(|| v.span()).or(span);
+        item = v;
+    }
+    span.map(|s| line_at(doc.raw(), s.start))
 }
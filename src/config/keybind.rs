@@ -27,10 +27,15 @@ pub fn get_message(s: &str) -> Option<Message> {
         "quit" => Some(Message::SwitchState(State::Done)),
         "switch_to_library" => Some(Message::SwitchScreen(Screen::Library)),
         "switch_to_queue" => Some(Message::SwitchScreen(Screen::Queue)),
+        "switch_to_playlists" => Some(Message::SwitchScreen(Screen::Playlists)),
+        "switch_to_directories" => {
+            Some(Message::SwitchScreen(Screen::Directories))
+        }
         "toggle_screen_lq" => Some(Message::ToggleScreen),
         "toggle_panel" => Some(Message::TogglePanel),
         "fold" => Some(Message::Fold),
         "clear_queue" => Some(Message::Clear),
+        "save_queue" => Some(Message::SaveQueue),
         "local_search" => Some(Message::LocalSearch(SearchMsg::Start)),
         "global_search" => Some(Message::GlobalSearch(SearchMsg::Start)),
         "escape" => Some(Message::Escape),
@@ -43,6 +48,13 @@ pub fn get_message(s: &str) -> Option<Message> {
         "previous_song" => Some(Message::PreviousSong),
         "seek" => Some(Message::Seek(SeekDirection::Forward)),
         "seek_backwards" => Some(Message::Seek(SeekDirection::Backward)),
+        "load_playlist" => Some(Message::Playlist(PlaylistMsg::Load)),
+        "append_playlist" => Some(Message::Playlist(PlaylistMsg::Append)),
+        "rename_playlist" => Some(Message::Playlist(PlaylistMsg::Rename)),
+        "delete_playlist" => Some(Message::Playlist(PlaylistMsg::Delete)),
+        "add_to_playlist" => Some(Message::AddToPlaylist),
+        "switch_server" => Some(Message::SwitchServer),
+        "reload_config" => Some(Message::ReloadConfig),
         _ => None,
     }
 }
@@ -90,6 +102,26 @@ impl KeybindMap {
             KeyEvent::new(KeyCode::Char('2'), EMPTY),
             Msg(SwitchScreen(super::Screen::Queue)),
         );
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('3'), EMPTY),
+            Msg(SwitchScreen(super::Screen::Playlists)),
+        );
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('4'), EMPTY),
+            Msg(SwitchScreen(super::Screen::Directories)),
+        );
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('a'), EMPTY),
+            Msg(Playlist(PlaylistMsg::Append)),
+        );
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('A'), EMPTY),
+            Msg(AddToPlaylist),
+        );
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('R'), EMPTY),
+            Msg(Playlist(PlaylistMsg::Rename)),
+        );
         keybindings.insert(
             KeyEvent::new(KeyCode::Char('q'), EMPTY),
             Msg(SwitchState(super::State::Done)),
@@ -101,6 +133,16 @@ impl KeybindMap {
         keybindings.insert(KeyEvent::new(KeyCode::Char(' '), EMPTY), Msg(Fold));
         keybindings
             .insert(KeyEvent::new(KeyCode::Char('-'), EMPTY), Msg(Clear));
+        keybindings
+            .insert(KeyEvent::new(KeyCode::Char('S'), EMPTY), Msg(SaveQueue));
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('P'), EMPTY),
+            Msg(SwitchServer),
+        );
+        keybindings.insert(
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+            Msg(ReloadConfig),
+        );
         keybindings.insert(
             KeyEvent::new(KeyCode::Char('/'), EMPTY),
             Msg(LocalSearch(SearchMsg::Start)),
@@ -256,36 +298,20 @@ pub fn parse_keybind_single(s: &str) -> Option<KeyCode> {
 pub fn parse_keybind(s: String) -> Result<Vec<KeyEvent>> {
     let mut out: Vec<KeyEvent> = Vec::new();
     for word in s.split(' ') {
-        if let Some(suffix) = word.strip_prefix("C-") {
-            out.push(KeyEvent::new(
-                parse_keybind_single(suffix)
-                    .unwrap_or_else(|| panic!("couldn't parse {}", word)),
-                KeyModifiers::CONTROL,
-            ))
+        let (modifiers, key) = if let Some(suffix) = word.strip_prefix("C-M-") {
+            (KeyModifiers::CONTROL | KeyModifiers::META, suffix)
+        } else if let Some(suffix) = word.strip_prefix("C-") {
+            (KeyModifiers::CONTROL, suffix)
         } else if let Some(suffix) = word.strip_prefix("M-") {
-            out.push(KeyEvent::new(
-                parse_keybind_single(suffix)
-                    .unwrap_or_else(|| panic!("couldn't parse {}", word)),
-                KeyModifiers::META,
-            ))
+            (KeyModifiers::META, suffix)
         } else if let Some(suffix) = word.strip_prefix("S-") {
-            out.push(KeyEvent::new(
-                parse_keybind_single(suffix)
-                    .unwrap_or_else(|| panic!("couldn't parse {}", word)),
-                KeyModifiers::SUPER,
-            ))
-        } else if let Some(suffix) = word.strip_prefix("C-M-") {
-            out.push(KeyEvent::new(
-                parse_keybind_single(suffix)
-                    .unwrap_or_else(|| panic!("couldn't parse {}", word)),
-                KeyModifiers::CONTROL | KeyModifiers::META,
-            ))
+            (KeyModifiers::SUPER, suffix)
         } else {
-            out.push(KeyEvent::new(
-                parse_keybind_single(word)
-                    .unwrap_or_else(|| panic!("couldn't parse {}", word)),
-                KeyModifiers::empty(),
-            ))
+            (KeyModifiers::empty(), word)
+        };
+        match parse_keybind_single(key) {
+            Some(code) => out.push(KeyEvent::new(code, modifiers)),
+            None => return Err(format!("couldn't parse key {:?}", word).into()),
         }
     }
     Ok(out)
@@ -0,0 +1,324 @@
+extern crate mpd;
+use mpd::error::{Error, ErrorCode, Result, ServerError};
+use mpd::Client;
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+// either end of a connection to mpd.
+pub enum Stream {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.read(buf),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(s) => s.write(buf),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.flush(),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.flush(),
+        }
+    }
+}
+
+impl Stream {
+    pub fn try_clone(&self) -> io::Result<Stream> {
+        match self {
+            Stream::Tcp(s) => s.try_clone().map(Stream::Tcp),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.try_clone().map(Stream::Unix),
+        }
+    }
+    // unblocks anyone reading from a clone of this stream
+    pub fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(s) => s.shutdown(Shutdown::Both),
+            #[cfg(unix)]
+            Stream::Unix(s) => s.shutdown(Shutdown::Both),
+        }
+    }
+}
+
+pub type MpdClient = Client<Stream>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Server {
+    // the profile from `[servers.<name>]`, if any
+    pub name: Option<String>,
+    // a hostname, a socket path starting with `/`, or an abstract socket
+    // name starting with `@`. When unset, the usual local sockets are
+    // tried before localhost.
+    pub host: Option<String>,
+    pub port: String,
+    pub password: Option<String>,
+}
+
+impl Server {
+    // host and port fall back to `MPD_HOST` and `MPD_PORT`. Follows the mpc
+    // convention of `password@host`; a password given there takes
+    // precedence over the one from the config file.
+    pub fn new(
+        host: Option<String>,
+        port: Option<String>,
+        password: Option<String>,
+    ) -> Self {
+        let (password, host) = match host.or_else(|| env::var("MPD_HOST").ok())
+        {
+            Some(host) => match host.split_once('@') {
+                Some((pw, rest)) if !pw.is_empty() => {
+                    (Some(pw.to_string()), Some(rest.to_string()))
+                }
+                _ => (password, Some(host)),
+            },
+            None => (password, None),
+        };
+        Server {
+            name: None,
+            host,
+            port: port
+                .or_else(|| env::var("MPD_PORT").ok())
+                .unwrap_or_else(|| "6600".to_string()),
+            password,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        match self.host.as_deref() {
+            Some(h) if h.starts_with(['/', '@']) => h.to_string(),
+            Some(h) => format!("{}:{}", h, self.port),
+            None => format!("localhost:{}", self.port),
+        }
+    }
+}
+
+fn default_sockets() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(runtime) = env::var_os("XDG_RUNTIME_DIR") {
+        paths.push(PathBuf::from(runtime).join("mpd").join("socket"));
+    }
+    paths.push(PathBuf::from("/run/mpd/socket"));
+    paths
+}
+
+#[cfg(unix)]
+fn open_socket(path: &Path) -> io::Result<Stream> {
+    UnixStream::connect(path).map(Stream::Unix)
+}
+
+#[cfg(not(unix))]
+fn open_socket(_path: &Path) -> io::Result<Stream> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "unix sockets are not supported on this platform",
+    ))
+}
+
+#[cfg(all(unix, any(target_os = "linux", target_os = "android")))]
+fn open_abstract(name: &str) -> io::Result<Stream> {
+    #[cfg(target_os = "android")]
+    use std::os::android::net::SocketAddrExt;
+    #[cfg(target_os = "linux")]
+    use std::os::linux::net::SocketAddrExt;
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+    UnixStream::connect_addr(&addr).map(Stream::Unix)
+}
+
+#[cfg(not(all(unix, any(target_os = "linux", target_os = "android"))))]
+fn open_abstract(_name: &str) -> io::Result<Stream> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "abstract sockets are not supported on this platform",
+    ))
+}
+
+pub fn open_stream(server: &Server) -> io::Result<Stream> {
+    open_endpoint(server).map(|(stream, _)| stream)
+}
+
+// also says where the stream went, which only shows once connected when
+// no host is given
+pub fn open_endpoint(server: &Server) -> io::Result<(Stream, String)> {
+    let url = server.url();
+    match server.host.as_deref() {
+        Some(name) if name.starts_with('@') => {
+            open_abstract(&name[1..]).map(|s| (s, url))
+        }
+        Some(path) if path.starts_with('/') => {
+            open_socket(Path::new(path)).map(|s| (s, url))
+        }
+        Some(_) => TcpStream::connect(&url).map(|s| (Stream::Tcp(s), url)),
+        None => match default_sockets()
+            .into_iter()
+            .find_map(|p| open_socket(&p).ok().map(|s| (s, p)))
+        {
+            Some((stream, path)) => {
+                Ok((stream, path.to_string_lossy().into_owned()))
+            }
+            None => TcpStream::connect(&url).map(|s| (Stream::Tcp(s), url)),
+        },
+    }
+}
+
+// the client, and the address it is connected to
+pub fn connect(server: &Server) -> Result<(MpdClient, String)> {
+    let (stream, url) = open_endpoint(server)?;
+    Ok((connect_stream(server, stream)?, url))
+}
+
+pub fn connect_stream(server: &Server, stream: Stream) -> Result<MpdClient> {
+    let mut client = Client::new(stream)?;
+    if let Some(password) = &server.password {
+        client.login(password)?;
+    }
+    Ok(client)
+}
+
+pub fn is_permission_error(e: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(
+        e.downcast_ref::<Error>(),
+        Some(Error::Server(ServerError {
+            code: ErrorCode::Permission | ErrorCode::Password,
+            ..
+        }))
+    )
+}
+
+pub fn permission_message(e: &(dyn std::error::Error + 'static)) -> String {
+    format!(
+        "permission denied ({}): set a password with MPD_HOST=password@host \
+         or `password` in config.toml",
+        e
+    )
+}
+
+// Anything but an ACK from the server means the socket can't be trusted
+// anymore.
+pub fn is_connection_error(e: &(dyn std::error::Error + 'static)) -> bool {
+    match e.downcast_ref::<Error>() {
+        Some(Error::Server(_)) => false,
+        Some(_) => true,
+        None => e.is::<io::Error>(),
+    }
+}
+
+pub struct Connection {
+    pub server: Server,
+    // where the last connection went, which can differ from
+    // `server.url()` when the default sockets were tried
+    endpoint: Option<String>,
+    // why the last connection attempt failed, if it did
+    pub last_error: Option<String>,
+    client: Option<MpdClient>,
+    backoff: Duration,
+    retry_at: Instant,
+}
+
+impl Connection {
+    pub fn new(server: Server) -> Self {
+        let mut conn = Self {
+            server,
+            endpoint: None,
+            last_error: None,
+            client: None,
+            backoff: MIN_BACKOFF,
+            retry_at: Instant::now(),
+        };
+        match connect(&conn.server) {
+            Ok((client, url)) => {
+                conn.client = Some(client);
+                conn.endpoint = Some(url);
+            }
+            Err(e) => {
+                conn.last_error = Some(e.to_string());
+                conn.disconnect();
+            }
+        }
+        conn
+    }
+
+    pub fn client(&mut self) -> Result<&mut MpdClient> {
+        let (endpoint, server) = (&self.endpoint, &self.server);
+        self.client.as_mut().ok_or_else(|| {
+            let url = endpoint.clone().unwrap_or_else(|| server.url());
+            Error::Io(io::Error::new(
+                io::ErrorKind::NotConnected,
+                format!("not connected to mpd at {}", url),
+            ))
+        })
+    }
+
+    pub fn url(&self) -> String {
+        self.endpoint.clone().unwrap_or_else(|| self.server.url())
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_some()
+    }
+
+    // idempotent: only the first call after losing the connection
+    // schedules a retry.
+    pub fn disconnect(&mut self) {
+        if self.client.take().is_some() {
+            self.backoff = MIN_BACKOFF;
+        }
+        if self.retry_at <= Instant::now() {
+            self.retry_at = Instant::now() + self.backoff;
+        }
+    }
+
+    pub fn retry_in(&self) -> Option<Duration> {
+        match self.client {
+            Some(_) => None,
+            None => {
+                Some(self.retry_at.saturating_duration_since(Instant::now()))
+            }
+        }
+    }
+
+    // returns true if a new connection was made
+    pub fn try_reconnect(&mut self) -> bool {
+        if self.client.is_some() || Instant::now() < self.retry_at {
+            return false;
+        }
+        match connect(&self.server) {
+            Ok((client, url)) => {
+                self.client = Some(client);
+                self.endpoint = Some(url);
+                self.last_error = None;
+                self.backoff = MIN_BACKOFF;
+                true
+            }
+            Err(e) => {
+                self.last_error = Some(e.to_string());
+                self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+                self.retry_at = Instant::now() + self.backoff;
+                false
+            }
+        }
+    }
+}
@@ -1,4 +1,11 @@
+use crate::connection::{connect_stream, open_stream, Server, Stream};
+use crate::model::{LibraryCache, LibraryLoader, Matches, SearchWorker};
+use mpd::{Idle, Subsystem};
 use ratatui::crossterm;
+use ratatui::crossterm::event::MouseEventKind;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
@@ -6,9 +13,35 @@ pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 pub enum Event {
     Tick,
     Key(crossterm::event::KeyEvent),
+    Mouse(crossterm::event::MouseEvent),
+    Paste(String),
+    Focus(bool),
+    Player,
+    Queue,
+    Database,
+    Options,
+    Mixer,
+    StoredPlaylist,
+    Matches(Matches),
+    Library(Box<LibraryCache>),
+}
+
+impl Event {
+    fn from_subsystem(s: Subsystem) -> Option<Self> {
+        match s {
+            Subsystem::Player => Some(Event::Player),
+            Subsystem::Queue => Some(Event::Queue),
+            Subsystem::Database => Some(Event::Database),
+            Subsystem::Options => Some(Event::Options),
+            Subsystem::Mixer => Some(Event::Mixer),
+            Subsystem::Playlist => Some(Event::StoredPlaylist),
+            _ => None,
+        }
+    }
 }
 
 pub struct EventHandler {
+    tx: Sender<Event>,
     rx: std::sync::mpsc::Receiver<Event>,
 }
 
@@ -18,6 +51,7 @@ impl EventHandler {
         let tick_interval = Duration::from_millis(500);
 
         let (tx, rx) = std::sync::mpsc::channel();
+        let key_tx = tx.clone();
         let mut now = Instant::now();
         let mut last_event = Instant::now();
         std::thread::spawn(move || loop {
@@ -25,10 +59,28 @@ impl EventHandler {
                 match crossterm::event::read().expect("event read failed") {
                     crossterm::event::Event::Key(e) => {
                         last_event = Instant::now();
-                        tx.send(Event::Key(e))
+                        key_tx.send(Event::Key(e))
+                    }
+                    crossterm::event::Event::Mouse(e) => {
+                        last_event = Instant::now();
+                        match e.kind {
+                            MouseEventKind::Moved | MouseEventKind::Drag(_) => {
+                                Ok(())
+                            }
+                            _ => key_tx.send(Event::Mouse(e)),
+                        }
+                    }
+                    crossterm::event::Event::Paste(s) => {
+                        last_event = Instant::now();
+                        key_tx.send(Event::Paste(s))
                     }
-                    crossterm::event::Event::Resize(_, _) => Ok(()),
-                    _ => unimplemented!(),
+                    crossterm::event::Event::FocusGained => {
+                        key_tx.send(Event::Focus(true))
+                    }
+                    crossterm::event::Event::FocusLost => {
+                        key_tx.send(Event::Focus(false))
+                    }
+                    _ => Ok(()),
                 }
                 .expect("event send failed")
             }
@@ -36,14 +88,92 @@ impl EventHandler {
             if now.elapsed() >= tick_interval
                 && (Instant::now() - last_event >= Duration::from_millis(500))
             {
-                tx.send(Event::Tick).expect("tick send failed");
+                key_tx.send(Event::Tick).expect("tick send failed");
                 now = Instant::now();
             }
         });
-        EventHandler { rx }
+        EventHandler { tx, rx }
+    }
+
+    pub fn search_worker(&self) -> SearchWorker {
+        SearchWorker::new(self.tx.clone())
+    }
+
+    pub fn library_loader(&self) -> LibraryLoader {
+        LibraryLoader::new(self.tx.clone())
+    }
+
+    // blocks a second connection on `idle`, so that changes made by any
+    // client are picked up as soon as they happen. The connection is
+    // re-established if it drops, until the listener is stopped.
+    pub fn listen_idle(&self, server: Server) -> IdleListener {
+        let listener = IdleListener {
+            server: server.clone(),
+            stopped: Arc::new(AtomicBool::new(false)),
+            socket: Arc::new(Mutex::new(None)),
+        };
+        let stopped = listener.stopped.clone();
+        let socket = listener.socket.clone();
+        let tx = self.tx.clone();
+        std::thread::spawn(move || {
+            let subsystems = [
+                Subsystem::Player,
+                Subsystem::Queue,
+                Subsystem::Database,
+                Subsystem::Options,
+                Subsystem::Mixer,
+                Subsystem::Playlist,
+            ];
+            while !stopped.load(Ordering::SeqCst) {
+                let conn = open_stream(&server).and_then(|stream| {
+                    *socket.lock().unwrap() = Some(stream.try_clone()?);
+                    Ok(stream)
+                });
+                // stop() may have run before the clone was stored
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(mut conn) = conn
+                    .map_err(Into::into)
+                    .and_then(|s| connect_stream(&server, s))
+                {
+                    while let Ok(changed) = conn.wait(&subsystems) {
+                        for event in changed
+                            .into_iter()
+                            .filter_map(Event::from_subsystem)
+                        {
+                            if tx.send(event).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        });
+        listener
     }
 
     pub fn next(&self) -> Result<Event> {
         Ok(self.rx.recv()?)
     }
 }
+
+pub struct IdleListener {
+    server: Server,
+    stopped: Arc<AtomicBool>,
+    socket: Arc<Mutex<Option<Stream>>>,
+}
+
+impl IdleListener {
+    pub fn server(&self) -> &Server {
+        &self.server
+    }
+
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(socket) = self.socket.lock().unwrap().take() {
+            let _ = socket.shutdown();
+        }
+    }
+}
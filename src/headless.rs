@@ -0,0 +1,273 @@
+use crate::cli::{Command, StatusFormat, StatusOpts};
+use crate::connection::is_connection_error;
+use crate::event_handler::Result;
+use crate::model::proto::Searchable;
+use crate::model::{Model, Screen};
+use crate::update::{self, Message, SearchMsg, SeekDirection, Update};
+use crate::util::{format_progress, format_status, format_time, song_album};
+use mpd::State::*;
+use mpd::{Idle, Status, Subsystem};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::io::{self, Write};
+use std::thread;
+use std::time::Duration;
+
+// runs one command against the server the interface would connect to,
+// going through the same messages as the matching keybinding.
+pub fn run(model: &mut Model, command: Command) -> Result<()> {
+    // the queue screen is the cheapest to keep updated; the library is
+    // only built when a command needs it.
+    model.screen = Screen::Queue;
+    if let Command::Status(StatusOpts {
+        follow: true,
+        format,
+    }) = command
+    {
+        return follow(model, format);
+    }
+    update::update_screens(model, Update::STATUS | Update::CURRENT_SONG)?;
+    let update = match command {
+        Command::Play => match model.status.state {
+            Play => Update::empty(),
+            Pause => update::handle_msg(model, Message::PlayPause)?,
+            Stop => Update::START_PLAYING,
+        },
+        Command::Pause => match model.status.state {
+            Play => update::handle_msg(model, Message::PlayPause)?,
+            Pause | Stop => Update::empty(),
+        },
+        Command::Toggle => match model.status.state {
+            Stop => Update::START_PLAYING,
+            Play | Pause => update::handle_msg(model, Message::PlayPause)?,
+        },
+        Command::Next => update::handle_msg(model, Message::NextSong)?,
+        Command::Prev => update::handle_msg(model, Message::PreviousSong)?,
+        Command::Seek(secs) => {
+            if let Some(s) = secs {
+                model.config.seek_seconds = s;
+            }
+            let direction = match model.config.seek_seconds < 0 {
+                true => SeekDirection::Backward,
+                false => SeekDirection::Forward,
+            };
+            update::handle_msg(model, Message::Seek(direction))?
+        }
+        Command::Clear => update::handle_msg(model, Message::Clear)?,
+        Command::Add(query) => add(model, &query)?,
+        Command::Status(opts) => {
+            match &opts.format {
+                Some(format) => println!("{}", format_line(model, format)),
+                None => print_status(model),
+            }
+            Update::empty()
+        }
+    };
+    update::update_screens(model, update)
+}
+
+// pastes the query into the global search, moves to the top match and
+// selects it. The query is not remembered in the search history.
+fn add(model: &mut Model, query: &str) -> Result<Update> {
+    model.screen = Screen::Library;
+    update::update_screens(model, Update::DATABASE)?;
+    update::handle_msg(model, Message::GlobalSearch(SearchMsg::Start))?;
+    update::handle_paste(model, query.to_string())?;
+    update::handle_key(
+        model,
+        KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL),
+    )?;
+    if model.library.global_search.selected_item().is_none() {
+        return Err(format!("nothing matches \"{}\"", query).into());
+    }
+    update::handle_msg(model, Message::Select)
+}
+
+const FOLLOW_TEMPLATE: &str = "[{state}] {artist} - {title} {progress}";
+
+// prints a line whenever the status changes, riding out disconnects so
+// that bars can keep a single process running.
+fn follow(model: &mut Model, format: Option<StatusFormat>) -> Result<()> {
+    let format =
+        format.unwrap_or(StatusFormat::Template(FOLLOW_TEMPLATE.to_string()));
+    let mut out = io::stdout().lock();
+    let mut last = None;
+    let mut res =
+        update::update_screens(model, Update::STATUS | Update::CURRENT_SONG);
+    loop {
+        if let Err(e) = res {
+            if !is_connection_error(e.as_ref()) {
+                return Err(e);
+            }
+            model.conn.disconnect();
+            model.status = Status::default();
+            model.currentsong = None;
+        }
+        let line = format_line(model, &format);
+        if last.as_ref() != Some(&line) {
+            writeln!(out, "{}", line)?;
+            out.flush()?;
+            last = Some(line);
+        }
+        res = wait_for_change(model);
+    }
+}
+
+// blocks until the player or its options change, reconnecting as needed
+fn wait_for_change(model: &mut Model) -> Result<()> {
+    if let Some(retry) = model.conn.retry_in() {
+        thread::sleep(retry);
+        if !model.conn.try_reconnect() {
+            return Ok(());
+        }
+    } else {
+        model
+            .conn
+            .client()?
+            .wait(&[Subsystem::Player, Subsystem::Options])?;
+    }
+    update::update_screens(model, Update::STATUS | Update::CURRENT_SONG)
+}
+
+fn state_name(model: &Model) -> &'static str {
+    if !model.conn.is_connected() {
+        return "disconnected";
+    }
+    match model.status.state {
+        Play => "playing",
+        Pause => "paused",
+        Stop => "stopped",
+    }
+}
+
+fn format_line(model: &Model, format: &StatusFormat) -> String {
+    let song = model.currentsong.as_ref();
+    let title = song.and_then(|s| s.title.as_deref());
+    let artist = song.and_then(|s| s.artist.as_deref());
+    let album = song.and_then(song_album).map(|a| a.as_str());
+    let status = &model.status;
+    match format {
+        StatusFormat::Json => {
+            let string = |s: Option<&str>| s.map_or("null".into(), json_string);
+            let secs = |d: Option<Duration>| {
+                d.map_or("null".into(), |d| d.as_secs().to_string())
+            };
+            format!(
+                "{{\"state\":\"{}\",\"title\":{},\"artist\":{},\
+                 \"album\":{},\"elapsed\":{},\"duration\":{},\
+                 \"progress\":{},\"repeat\":{},\"random\":{},\
+                 \"single\":{},\"consume\":{}}}",
+                state_name(model),
+                string(title),
+                string(artist),
+                string(album),
+                secs(status.elapsed),
+                secs(status.duration),
+                json_string(&format_progress(status)),
+                status.repeat,
+                status.random,
+                status.single,
+                status.consume,
+            )
+        }
+        StatusFormat::Template(t) => {
+            let time = |d: Option<Duration>| d.map(format_time);
+            fill_template(t, |key| {
+                Some(
+                    match key {
+                        "title" => title.map(str::to_string),
+                        "artist" => artist.map(str::to_string),
+                        "album" => album.map(str::to_string),
+                        "state" => Some(state_name(model).to_string()),
+                        "elapsed" => time(status.elapsed),
+                        "duration" => time(status.duration),
+                        "progress" => Some(format_progress(status)),
+                        "repeat" => Some(format_status(status.repeat)),
+                        "random" => Some(format_status(status.random)),
+                        "single" => Some(format_status(status.single)),
+                        "consume" => Some(format_status(status.consume)),
+                        _ => return None,
+                    }
+                    .unwrap_or_default(),
+                )
+            })
+        }
+    }
+}
+
+// replaces `{key}` with its value; unknown keys are kept as they are.
+fn fill_template(t: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = t;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        match rest[start..].find('}') {
+            Some(len) => {
+                let key = &rest[start + 1..start + len];
+                match value(key) {
+                    Some(v) => out.push_str(&v),
+                    None => out.push_str(&rest[start..=start + len]),
+                }
+                rest = &rest[start + len + 1..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn print_status(model: &Model) {
+    if let Some(song) = &model.currentsong {
+        println!(
+            "{} - {} ({})",
+            song.artist.as_deref().unwrap_or("<ARTIST NOT FOUND>"),
+            song.title.as_deref().unwrap_or("<TITLE NOT FOUND>"),
+            song_album(song).map_or("<ALBUM NOT FOUND>", |a| a.as_str()),
+        );
+    }
+    let state = match model.status.state {
+        Play => "[playing]",
+        Pause => "[paused]",
+        Stop => "[stopped]",
+    };
+    match model.status.song {
+        Some(pos) => println!(
+            "{} #{}/{} {}",
+            state,
+            pos.pos + 1,
+            model.status.queue_len,
+            format_progress(&model.status)
+        ),
+        None => println!("{}", state),
+    }
+    let on_off = |b| if b { "on" } else { "off" };
+    println!(
+        "repeat: {} random: {} single: {} consume: {}",
+        on_off(model.status.repeat),
+        on_off(model.status.random),
+        on_off(model.status.single),
+        on_off(model.status.consume),
+    );
+}
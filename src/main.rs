@@ -1,8 +1,13 @@
 extern crate mpd;
+use connection::{is_permission_error, permission_message};
 use model::State;
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
+        event::{
+            DisableBracketedPaste, DisableFocusChange, DisableMouseCapture,
+            EnableBracketedPaste, EnableFocusChange, EnableMouseCapture,
+        },
         terminal::{
             disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
             LeaveAlternateScreen,
@@ -11,44 +16,112 @@ use ratatui::{
     },
     Terminal,
 };
-use std::io::stdout;
-use update::Update;
+use std::io::{stdout, Stdout};
+mod cli;
 mod config;
+mod connection;
 mod event_handler;
+mod headless;
 mod model;
 mod update;
 mod util;
 mod view;
-use event_handler::{Event, Result};
+use event_handler::Result;
 
-fn main() -> Result<()> {
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-    terminal.clear()?;
+fn restore_terminal() -> Result<()> {
+    stdout().execute(DisableMouseCapture)?;
+    stdout().execute(DisableBracketedPaste)?;
+    stdout().execute(DisableFocusChange)?;
+    stdout().execute(LeaveAlternateScreen)?;
+    disable_raw_mode()?;
+    Ok(())
+}
 
-    let mut model = model::Model::new().expect("Failed to init.");
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    args: cli::Args,
+) -> Result<()> {
+    let mut model = model::Model::new(&args);
+    let event_handler = event_handler::EventHandler::new();
+    model.library.global_search.worker = Some(event_handler.search_worker());
+    model.library.loader = Some(event_handler.library_loader());
 
-    update::update_tick(&mut model)?;
-    update::update_screens(&mut model, Update::empty())?;
+    update::refresh_all(&mut model)?;
     terminal.draw(|f| view::view(&mut model, f))?;
 
-    let event_handler = event_handler::EventHandler::new();
+    let mut idle = event_handler.listen_idle(model.conn.server.clone());
     loop {
-        match event_handler.next()? {
-            Event::Tick => update::update_tick(&mut model)?,
-            Event::Key(k) => {
-                let update = update::handle_key(&mut model, k)?;
-                update::update_screens(&mut model, update)?;
-            }
+        update::update_event(&mut model, event_handler.next()?)?;
+        if idle.server() != &model.conn.server {
+            idle.stop();
+            idle = event_handler.listen_idle(model.conn.server.clone());
         }
         terminal.draw(|f| view::view(&mut model, f))?;
         if let State::Done = model.state {
             break;
         }
     }
-
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
     Ok(())
 }
+
+fn main() -> Result<()> {
+    let mut args = match cli::parse(std::env::args().skip(1)) {
+        Ok(cli::Parsed::Run(args)) => args,
+        Ok(cli::Parsed::Help) => {
+            print!("{}", cli::usage());
+            return Ok(());
+        }
+        Ok(cli::Parsed::Version) => {
+            println!("{}", cli::version());
+            return Ok(());
+        }
+        Err(e) => {
+            eprint!("inori: {}\n\n{}", e, cli::usage());
+            std::process::exit(2);
+        }
+    };
+    if let Some(path) = cli::config_path(&args).filter(|p| !p.is_file()) {
+        eprintln!("inori: config file {} not found", path.display());
+        std::process::exit(2);
+    }
+    if let Some(command) = args.command.take() {
+        let mut model = model::Model::new(&args);
+        for e in &model.config.errors {
+            eprintln!("inori: config: {}", e);
+        }
+        if let Err(e) = headless::run(&mut model, command) {
+            match &model.conn.last_error {
+                Some(reason) if !model.conn.is_connected() => eprintln!(
+                    "inori: could not connect to {}: {}",
+                    model.conn.url(),
+                    reason
+                ),
+                _ if is_permission_error(e.as_ref()) => {
+                    eprintln!("inori: {}", permission_message(e.as_ref()))
+                }
+                _ => eprintln!("inori: {}", e),
+            }
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // leave the terminal usable even if something goes badly wrong
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
+
+    stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableMouseCapture)?;
+    stdout().execute(EnableBracketedPaste)?;
+    stdout().execute(EnableFocusChange)?;
+    enable_raw_mode()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+    terminal.clear()?;
+
+    let res = run(&mut terminal, args);
+    restore_terminal()?;
+    res
+}
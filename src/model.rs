@@ -1,30 +1,49 @@
 extern crate mpd;
 use mpd::error::Result;
-use mpd::{Client, Song, Status};
-use nucleo_matcher::{Matcher, Utf32String};
+use mpd::{Playlist, Song, Status};
+use nucleo_matcher::Utf32String;
 use ratatui::crossterm::event::KeyEvent;
+use ratatui::layout::Rect;
 use ratatui::widgets::*;
-use std::env;
 mod impl_album_song;
 mod impl_artiststate;
+mod impl_directory;
+mod impl_history;
 mod impl_library;
+mod impl_playlist;
+mod impl_prompt;
 mod impl_queue;
 mod impl_searchstate;
+pub mod library_cache;
 pub mod proto;
+#[cfg(feature = "cjk")]
+mod reading;
 mod search_utils;
+mod search_worker;
+use crate::cli::{config_path, Args};
 use crate::config::Config;
+use crate::connection::{Connection, Server};
 use crate::model::proto::*;
 use crate::update::build_library;
+pub use library_cache::{LibraryCache, LibraryLoader};
+pub use search_utils::SearchMatcher;
+pub use search_worker::{Matches, SearchWorker};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub enum Screen {
     Library,
     Queue,
+    Playlists,
+    Directories,
 }
 
 #[derive(Clone, Debug)]
 pub enum State {
     Searching,
+    Prompting,
     Running,
     Done,
 }
@@ -33,6 +52,8 @@ pub enum State {
 pub struct AlbumData {
     pub expanded: bool,
     pub name: String,
+    // albumsort, if it differs from the name
+    pub sort_name: Option<String>,
     pub tracks: Vec<Song>,
 }
 
@@ -67,24 +88,34 @@ pub struct FilterCache {
     pub order: Vec<Option<usize>>,
     pub indices: Vec<Vec<u32>>,
     pub utfstrings_cache: Option<Vec<Utf32String>>,
+    // the last query scored against the strings and the indices that
+    // matched it, in order
+    pub matched: Option<(String, Vec<usize>)>,
 }
 
 pub struct Filter {
     pub active: bool,
     pub query: String,
+    // byte offset of the cursor in `query`
+    pub cursor: usize,
     pub cache: FilterCache,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct InfoEntry {
     pub artist: String,
     pub artist_sort: Option<String>,
     pub album: Option<String>,
     pub title: Option<String>,
+    // only matched by `genre:` and `date:`, not shown
+    pub genre: Option<String>,
+    pub date: Option<String>,
 }
 pub struct GlobalSearchState {
     pub search: Filter,
-    pub contents: Option<Vec<InfoEntry>>,
+    pub contents: Option<Arc<Vec<InfoEntry>>>,
+    // matches in the background if set, instead of on every keypress
+    pub worker: Option<SearchWorker>,
     pub results_state: ListState,
 }
 
@@ -94,6 +125,40 @@ pub struct LibraryState {
     pub active: LibActiveSelector,
     pub contents: Vec<ArtistData>,
     pub artist_state: ListState,
+    // mpd's db_update when the contents were fetched
+    pub db_update: Option<u64>,
+    // refetches the library in the background if set
+    pub loader: Option<LibraryLoader>,
+}
+
+#[derive(Debug)]
+pub struct DirData {
+    pub name: String,
+    pub path: String,
+    pub expanded: bool,
+    pub fetched: bool,
+    pub dirs: Vec<DirData>,
+    pub files: Vec<Song>,
+}
+
+#[derive(Debug)]
+pub enum DirItemRef<'a> {
+    Dir(&'a DirData),
+    File(&'a Song),
+}
+
+pub struct DirSelItem<'a> {
+    pub item: DirItemRef<'a>,
+    pub depth: usize,
+    pub rank: Option<usize>,
+}
+
+pub struct DirectoryState {
+    pub root: DirData,
+    // the database changed since the loaded directories were listed
+    pub stale: bool,
+    pub state: TableState,
+    pub search: Filter,
 }
 
 pub struct QueueSelector {
@@ -102,81 +167,272 @@ pub struct QueueSelector {
     pub state: TableState,
 }
 
+pub enum PlaylistActiveSelector {
+    PlaylistSelector,
+    TrackSelector,
+}
+
+pub struct PlaylistPreview {
+    pub name: Option<String>,
+    pub tracks: Vec<Song>,
+    pub state: TableState,
+}
+
+pub struct PlaylistSelector {
+    pub search: Filter,
+    pub contents: Vec<Playlist>,
+    pub state: ListState,
+    pub active: PlaylistActiveSelector,
+    pub preview: PlaylistPreview,
+}
+
+#[derive(Clone, Debug)]
+pub enum PromptAction {
+    RenamePlaylist(String),
+    SaveQueue,
+    OverwritePlaylist(String),
+    DeletePlaylist(String),
+    AddToPlaylist(Vec<Song>),
+    SwitchServer,
+}
+
+pub struct Completions {
+    pub search: Filter,
+    pub contents: Vec<String>,
+    pub state: ListState,
+}
+
+pub struct Prompt {
+    pub title: String,
+    pub input: String,
+    pub cursor: usize,
+    pub action: PromptAction,
+    pub completions: Option<Completions>,
+}
+
+// queries submitted in any search, oldest first
+pub struct History {
+    pub entries: Vec<String>,
+    // the entry being shown while going through the history, and what
+    // was typed before
+    browsing: Option<(usize, String)>,
+    path: Option<PathBuf>,
+}
+
+// where the clickable widgets were drawn in the last frame
+#[derive(Default)]
+pub struct ClickAreas {
+    pub artists: Rect,
+    pub tracks: Rect,
+    pub queue: Rect,
+    pub progress: Rect,
+}
+
+// a read-only message box, closed by any key
+pub struct Popup {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
 pub struct Model {
     pub state: State,
     pub status: Status,
-    pub conn: Client,
+    pub conn: Connection,
     pub screen: Screen,
     pub library: LibraryState,
     pub queue: QueueSelector,
+    pub playlists: PlaylistSelector,
+    pub directories: DirectoryState,
+    pub prompt: Option<Prompt>,
+    pub history: History,
+    pub popup: Option<Popup>,
     pub currentsong: Option<Song>,
-    pub matcher: nucleo_matcher::Matcher,
+    // shown in the header until the next keypress
+    pub message: Option<String>,
+    // whether the terminal has focus, if it reports it
+    pub focused: bool,
+    pub matcher: SearchMatcher,
     pub config: Config,
     pub parse_state: Vec<KeyEvent>,
     pub window_height: Option<usize>,
+    pub click_areas: ClickAreas,
 }
 
 impl Model {
-    pub fn new() -> Result<Self> {
-        let mpd_url = format!(
-            "{}:{}",
-            env::var("MPD_HOST").unwrap_or_else(|_| "localhost".to_string()),
-            env::var("MPD_PORT").unwrap_or_else(|_| "6600".to_string())
-        );
-        let mut conn = Client::connect(mpd_url.clone()).unwrap_or_else(|_| {
-            panic!("Failed to connect to mpd server at {}", mpd_url)
+    // nothing is asked of the server yet, so that refusals can be shown
+    // like any other
+    pub fn new(args: &Args) -> Self {
+        let config = Config::default().try_read_config(config_path(args));
+        // an explicit host or port wins over the configured default profile
+        let explicit = args.host.is_some()
+            || args.port.is_some()
+            || env::var_os("MPD_HOST").is_some()
+            || env::var_os("MPD_PORT").is_some();
+        let server = match (explicit, &config.default_server) {
+            (false, Some(name)) => config.server(name),
+            _ => None,
+        }
+        .unwrap_or_else(|| {
+            Server::new(
+                args.host.clone(),
+                args.port.clone(),
+                config.password.clone(),
+            )
         });
-
-        Ok(Model {
+        let config = match &server.name {
+            Some(name) => config.with_profile(name),
+            None => config,
+        };
+        Model {
             state: State::Running,
-            status: conn.status()?,
-            conn,
+            status: Status::default(),
+            conn: Connection::new(server),
             screen: Screen::Library,
             library: LibraryState::new(),
             queue: QueueSelector::new(),
+            playlists: PlaylistSelector::new(),
+            directories: DirectoryState::new(),
+            prompt: None,
+            history: History::load(),
+            popup: Popup::config_errors(&config),
             currentsong: None,
-            matcher: {
-                let mut default_config = nucleo_matcher::Config::DEFAULT;
-                default_config.prefer_prefix = true;
-                Matcher::new(default_config)
-            },
-            config: Config::default().try_read_config(),
+            message: None,
+            focused: true,
+            matcher: SearchMatcher::new(config.smart_case),
+            config,
             parse_state: Vec::new(),
             window_height: Some(100),
-        })
+            click_areas: ClickAreas::default(),
+        }
+    }
+    // drops everything fetched from the current server. Returns false if
+    // there is no such profile.
+    pub fn switch_server(&mut self, name: &str) -> bool {
+        let config =
+            Config::default().try_read_config(self.config.path.clone());
+        let Some(server) = config.server(name) else {
+            return false;
+        };
+        self.config = config.with_profile(name);
+        self.popup = Popup::config_errors(&self.config);
+        self.conn = Connection::new(server);
+        self.status = Status::default();
+        self.currentsong = None;
+        let worker = self.library.global_search.worker.take();
+        let loader = self.library.loader.take();
+        self.library = LibraryState::new();
+        self.library.global_search.worker = worker;
+        self.library.loader = loader;
+        self.queue = QueueSelector::new();
+        self.playlists = PlaylistSelector::new();
+        self.directories = DirectoryState::new();
+        self.parse_state.clear();
+        true
+    }
+    // keeps the connection and everything fetched from it. A file that
+    // can't be parsed at all leaves the current config in place.
+    pub fn reload_config(&mut self) {
+        let mut config =
+            Config::default().try_read_config(self.config.path.clone());
+        if let Some(name) = &self.conn.server.name {
+            config = config.with_profile(name);
+        }
+        self.popup = Popup::config_errors(&config);
+        if config.loaded() {
+            let case_changed = self.matcher.smart_case != config.smart_case;
+            self.matcher.smart_case = config.smart_case;
+            self.config = config;
+            self.parse_state.clear();
+            if case_changed {
+                self.refilter();
+            }
+            if self.popup.is_none() {
+                self.message = Some("config reloaded".to_string());
+            }
+        } else {
+            // don't report the same broken file on every tick
+            self.config.modified = config.modified;
+            if self.popup.is_none() {
+                self.message = Some("couldn't read config".to_string());
+            }
+        }
+    }
+    // drops what every filter matched, and searches again in the ones
+    // in use
+    fn refilter(&mut self) {
+        let (matcher, top_k) = (&mut self.matcher, self.window_height);
+        refilter(&mut self.library, matcher, top_k);
+        refilter(&mut self.library.global_search, matcher, top_k);
+        refilter(&mut self.queue, matcher, top_k);
+        refilter(&mut self.playlists, matcher, top_k);
+        if let Some(c) =
+            self.prompt.as_mut().and_then(|p| p.completions.as_mut())
+        {
+            refilter(c, matcher, None);
+        }
+        for artist in &mut self.library.contents {
+            refilter_ranked(artist, matcher);
+        }
+        refilter_ranked(&mut self.directories, matcher);
     }
     pub fn update_status(&mut self) -> Result<()> {
-        self.status = self.conn.status()?;
+        self.status = self.conn.client()?.status()?;
         Ok(())
     }
     pub fn update_currentsong(&mut self) -> Result<()> {
-        self.currentsong = self.conn.currentsong()?;
+        self.currentsong = self.conn.client()?.currentsong()?;
         Ok(())
     }
 
+    pub fn update_playlists(&mut self) -> Result<()> {
+        let playlists = self.conn.client()?.playlists().unwrap_or_default();
+        self.playlists.set_contents(playlists);
+        if let Some(name) = &self.playlists.preview.name {
+            self.playlists.preview.tracks =
+                self.conn.client()?.playlist(name).unwrap_or_default();
+        }
+        Ok(())
+    }
+
+    // cached along with the library it was fetched for
     pub fn update_global_search_contents(&mut self) -> Result<()> {
-        let mut res = self.conn.list_groups(vec![
-            "title",
-            "album",
-            "albumartistsort",
-            "albumartist",
-        ])?;
-        self.library.global_search.contents = Some(
-            res.iter_mut()
-                .filter_map(|vec| {
-                    let ie = InfoEntry::from(vec);
-                    if !ie.is_redundant() {
-                        Some(ie)
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<InfoEntry>>(),
-        );
+        let contents = build_library::fetch_entries(self.conn.client()?)?;
+        if let Some(db_update) = self.library.db_update {
+            library_cache::save(
+                &self.conn.url(),
+                db_update,
+                &self.library.contents,
+                &contents,
+            );
+        }
+        self.library.global_search.contents = Some(Arc::new(contents));
         Ok(())
     }
 
-    pub fn jump_to(&mut self, target: InfoEntry) {
+    // the search box that keys go to while searching
+    pub fn active_filter(&mut self) -> Option<&mut Filter> {
+        match self.screen {
+            Screen::Library if self.library.global_search.search.active => {
+                Some(&mut self.library.global_search.search)
+            }
+            Screen::Library => match self.library.active {
+                LibActiveSelector::ArtistSelector => {
+                    Some(&mut self.library.artist_search)
+                }
+                LibActiveSelector::TrackSelector => {
+                    self.library.selected_item_mut().map(|a| &mut a.search)
+                }
+            },
+            Screen::Queue => Some(&mut self.queue.search),
+            Screen::Playlists => Some(&mut self.playlists.search),
+            Screen::Directories => Some(&mut self.directories.search),
+        }
+    }
+
+    pub fn jump_to(
+        &mut self,
+        target: InfoEntry,
+    ) -> crate::event_handler::Result<()> {
         // order: albumartist albumartistsort album title
         let artist_idx = self
             .library
@@ -191,11 +447,10 @@ impl Model {
         }
 
         if target.album.is_none() {
-            return;
+            return Ok(());
         }
         if self.library.selected_item().is_some_and(|i| !i.fetched) {
-            build_library::add_tracks(self)
-                .expect("couldn't add tracks on the fly while searching");
+            build_library::add_tracks(self)?;
         }
         if let Some(artist) = self.library.selected_item_mut() {
             let mut idx: Option<usize> = None;
@@ -215,5 +470,24 @@ impl Model {
             }
             artist.set_selected(idx);
         }
+        Ok(())
+    }
+}
+
+fn refilter<T>(
+    s: &mut impl Searchable<T>,
+    matcher: &mut SearchMatcher,
+    top_k: Option<usize>,
+) {
+    s.filter_mut().cache.invalidate();
+    if s.filter().active {
+        s.update_filter_cache(matcher, top_k);
+    }
+}
+
+fn refilter_ranked(s: &mut impl RankedSelector, matcher: &mut SearchMatcher) {
+    s.search_mut().cache.invalidate();
+    if s.search_mut().active {
+        s.update_search(matcher);
     }
 }
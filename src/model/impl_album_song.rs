@@ -2,6 +2,15 @@ use super::*;
 use std::time::Duration;
 
 impl AlbumData {
+    pub fn sort_key(&self) -> &str {
+        self.sort_name.as_ref().unwrap_or(&self.name)
+    }
+    pub fn to_fuzzy_find_str(&self) -> String {
+        match &self.sort_name {
+            Some(sort) => format!("{} [{}]", self.name, sort),
+            None => self.name.clone(),
+        }
+    }
     pub fn total_time(&self) -> Duration {
         self.tracks
             .iter()
@@ -1,7 +1,7 @@
 use super::*;
-use crate::model::search_utils::compute_orders;
+use crate::util::song_search_title;
 use proto::*;
-use search_utils::compute_indices;
+use search_utils::with_readings;
 
 impl Selector for ArtistData {
     fn selector(&self) -> &impl SelectorState {
@@ -36,7 +36,7 @@ impl<'a> ArtistData {
             }
         }
     }
-    pub fn contents(&'a self) -> Vec<TrackSelItem> {
+    pub fn contents(&'a self) -> Vec<TrackSelItem<'a>> {
         let mut new: Vec<TrackSelItem> = Vec::new();
         let mut i = 0; // full index
         for album in &self.albums {
@@ -68,7 +68,7 @@ impl<'a> ArtistData {
         // }
         new
     }
-    pub fn selected_item(&self) -> Option<TrackSelItem> {
+    pub fn selected_item(&self) -> Option<TrackSelItem<'_>> {
         let sel_idx = self.selector().selected()?;
         let mut i = 0; // keeps track of index with folding
         let mut full_index = 0; // keeps track of index without considering folding
@@ -125,42 +125,22 @@ impl<'a> ArtistData {
         None
     }
 
-    pub fn update_search(&mut self, matcher: &mut Matcher) {
+    pub fn update_search(&mut self, matcher: &mut SearchMatcher) {
         if self.search.cache.query == self.search.query {
             return;
         }
         if self.search.cache.utfstrings_cache.is_none() {
             let mut tmp: Vec<Utf32String> = Vec::new();
             for album in &self.albums {
-                tmp.push(Utf32String::from(album.name.clone()));
+                tmp.push(with_readings(album.to_fuzzy_find_str()));
                 for track in &album.tracks {
-                    tmp.push(Utf32String::from(
-                        track.title.clone().unwrap_or("".into()),
-                    ));
+                    tmp.push(with_readings(song_search_title(track)));
                 }
             }
             self.search.cache.utfstrings_cache = Some(tmp);
         }
-        self.search.cache.order = compute_orders(
-            &self.search.query,
-            self.search.cache.utfstrings_cache.as_ref().unwrap(),
-            matcher,
-            0,
-        );
-        let strings_for_indices: Vec<&Utf32String> = self
-            .search
-            .cache
-            .order
-            .iter()
-            .take_while(|i| i.is_some())
-            .map(|i| {
-                &self.search.cache.utfstrings_cache.as_ref().unwrap()
-                    [i.unwrap()]
-            })
-            .collect();
-
-        self.search.cache.indices =
-            compute_indices(&self.search.query, strings_for_indices, matcher);
+        self.search.update_order(matcher, 0);
+        self.search.update_indices(matcher, None);
         self.search.cache.query = self.search.query.clone();
 
         if self.search.cache.order.iter().any(|i| i.is_some()) {
@@ -189,6 +169,18 @@ impl<'a> ArtistData {
     }
 }
 
+impl RankedSelector for ArtistData {
+    fn search_mut(&mut self) -> &mut Filter {
+        &mut self.search
+    }
+    fn ranks(&self) -> Vec<Option<usize>> {
+        self.contents().iter().map(|i| i.rank).collect()
+    }
+    fn update_search(&mut self, matcher: &mut SearchMatcher) {
+        ArtistData::update_search(self, matcher)
+    }
+}
+
 impl ArtistData {
     pub fn from_names(name: String, sort_names: Vec<String>) -> Self {
         Self {
@@ -0,0 +1,258 @@
+use super::*;
+use proto::*;
+use std::collections::HashMap;
+
+impl DirData {
+    pub fn new(path: String) -> Self {
+        Self {
+            name: path.rsplit('/').next().unwrap_or_default().to_string(),
+            path,
+            expanded: false,
+            fetched: false,
+            dirs: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    // pre-order walk over every fetched entry below self. `full` counts
+    // entries regardless of folding, the same way the track selector
+    // indexes its search strings.
+    fn walk<'a>(
+        &'a self,
+        depth: usize,
+        only_expanded: bool,
+        full: &mut usize,
+        out: &mut Vec<(usize, DirSelItem<'a>)>,
+    ) {
+        for dir in &self.dirs {
+            out.push((
+                *full,
+                DirSelItem {
+                    item: DirItemRef::Dir(dir),
+                    depth,
+                    rank: None,
+                },
+            ));
+            *full += 1;
+            if dir.expanded || !only_expanded {
+                dir.walk(depth + 1, only_expanded, full, out);
+            } else {
+                *full += dir.count();
+            }
+        }
+        for file in &self.files {
+            out.push((
+                *full,
+                DirSelItem {
+                    item: DirItemRef::File(file),
+                    depth,
+                    rank: None,
+                },
+            ));
+            *full += 1;
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.dirs.iter().map(|d| 1 + d.count()).sum::<usize>()
+            + self.files.len()
+    }
+
+    pub fn find_mut(&mut self, path: &str) -> Option<&mut DirData> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.dirs
+            .iter_mut()
+            .filter(|d| {
+                path.starts_with(&format!("{}/", d.path)) || d.path == path
+            })
+            .find_map(|d| d.find_mut(path))
+    }
+
+    // paths of self and every listed directory below it, parents first
+    pub fn fetched_paths(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if self.fetched {
+            out.push(self.path.clone());
+            for dir in &self.dirs {
+                out.extend(dir.fetched_paths());
+            }
+        }
+        out
+    }
+
+    fn fold_all(&mut self) {
+        for dir in &mut self.dirs {
+            dir.expanded = false;
+            dir.fold_all();
+        }
+    }
+}
+
+impl DirSelItem<'_> {
+    pub fn path(&self) -> &str {
+        match self.item {
+            DirItemRef::Dir(d) => &d.path,
+            DirItemRef::File(f) => &f.file,
+        }
+    }
+    pub fn name(&self) -> &str {
+        match self.item {
+            DirItemRef::Dir(d) => &d.name,
+            DirItemRef::File(f) => {
+                f.file.rsplit('/').next().unwrap_or_default()
+            }
+        }
+    }
+}
+
+impl Selector for DirectoryState {
+    fn selector(&self) -> &impl SelectorState {
+        &self.state
+    }
+    fn selector_mut(&mut self) -> &mut impl SelectorState {
+        &mut self.state
+    }
+    fn len(&self) -> usize {
+        self.contents().len()
+    }
+}
+
+impl DirectoryState {
+    pub fn new() -> Self {
+        Self {
+            root: DirData::new(String::new()),
+            stale: false,
+            state: TableState::default(),
+            search: Filter::new(),
+        }
+    }
+
+    fn find_rank(&self, idx: usize) -> Option<usize> {
+        self.search
+            .cache
+            .order
+            .iter()
+            .take_while(|i| i.is_some())
+            .position(|i| *i == Some(idx))
+    }
+
+    pub fn contents(&self) -> Vec<DirSelItem<'_>> {
+        let mut out = Vec::new();
+        self.root.walk(0, true, &mut 0, &mut out);
+        out.into_iter()
+            .map(|(full, mut item)| {
+                if self.search.active {
+                    item.rank = self.find_rank(full);
+                }
+                item
+            })
+            .collect()
+    }
+
+    pub fn selected_item(&self) -> Option<DirSelItem<'_>> {
+        let idx = self.selected()?;
+        self.contents().into_iter().nth(idx)
+    }
+
+    pub fn selected_dir_mut(&mut self) -> Option<&mut DirData> {
+        let path = match self.selected_item()?.item {
+            DirItemRef::Dir(d) => d.path.clone(),
+            DirItemRef::File(_) => return None,
+        };
+        self.root.find_mut(&path)
+    }
+
+    // index of the closest directory above the selection, if any
+    pub fn parent_idx(&self) -> Option<usize> {
+        let idx = self.selected()?;
+        let contents = self.contents();
+        let depth = contents.get(idx)?.depth;
+        (0..idx).rev().find(|i| {
+            contents[*i].depth < depth
+                && matches!(contents[*i].item, DirItemRef::Dir(_))
+        })
+    }
+
+    // subdirectories listed before keep their contents, so that a
+    // refresh doesn't fold them
+    pub fn insert(&mut self, mut dir: DirData) {
+        dir.fetched = true;
+        if let Some(node) = self.root.find_mut(&dir.path) {
+            dir.expanded = node.expanded;
+            let mut old: HashMap<String, DirData> =
+                node.dirs.drain(..).map(|d| (d.path.clone(), d)).collect();
+            for sub in &mut dir.dirs {
+                if let Some(old) = old.remove(&sub.path) {
+                    *sub = old;
+                }
+            }
+            *node = dir;
+        }
+        self.search.cache.utfstrings_cache = None;
+        self.search.cache.matched = None;
+    }
+
+    pub fn select_path(&mut self, path: &str) {
+        if let Some(idx) = self.contents().iter().position(|i| i.path() == path)
+        {
+            self.set_selected(Some(idx));
+        }
+        self.watch_oob();
+    }
+}
+
+impl RankedSelector for DirectoryState {
+    fn search_mut(&mut self) -> &mut Filter {
+        &mut self.search
+    }
+    fn ranks(&self) -> Vec<Option<usize>> {
+        self.contents().iter().map(|i| i.rank).collect()
+    }
+    fn update_search(&mut self, matcher: &mut SearchMatcher) {
+        if self.search.cache.query == self.search.query
+            && self.search.cache.utfstrings_cache.is_some()
+        {
+            return;
+        }
+        if self.search.cache.utfstrings_cache.is_none() {
+            let mut all = Vec::new();
+            self.root.walk(0, false, &mut 0, &mut all);
+            self.search.cache.utfstrings_cache = Some(
+                all.iter()
+                    .map(|(_, i)| Utf32String::from(i.name()))
+                    .collect(),
+            );
+        }
+        self.search.update_order(matcher, 0);
+        self.search.update_indices(matcher, None);
+        self.search.cache.query = self.search.query.clone();
+
+        if self.search.cache.order.iter().any(|i| i.is_some()) {
+            let mut all = Vec::new();
+            self.root.walk(0, false, &mut 0, &mut all);
+            let matched: Vec<String> = self
+                .search
+                .cache
+                .order
+                .iter()
+                .map_while(|i| *i)
+                .filter_map(|i| {
+                    all.get(i).map(|(_, item)| item.path().to_string())
+                })
+                .collect();
+            self.root.fold_all();
+            for path in matched {
+                // unfold every ancestor of the match
+                for (i, _) in path.match_indices('/') {
+                    if let Some(dir) = self.root.find_mut(&path[..i]) {
+                        dir.expanded = true;
+                    }
+                }
+            }
+        }
+        let top_idx = self.contents().iter().position(|i| i.rank == Some(0));
+        self.set_selected(top_idx);
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+use std::fs;
+use std::path::PathBuf;
+
+impl History {
+    // a missing or unreadable file just means there is no history yet
+    pub fn load() -> Self {
+        let path = dirs::data_dir().map(|mut p| {
+            p.push(PathBuf::from_iter(["inori", "search_history"]));
+            p
+        });
+        let entries = path
+            .as_ref()
+            .and_then(|p| fs::read_to_string(p).ok())
+            .map(|s| s.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        History {
+            entries,
+            browsing: None,
+            path,
+        }
+    }
+    // moves `query` to the newest entry, dropping the oldest ones past
+    // `size`
+    pub fn push(&mut self, query: &str, size: usize) {
+        self.browsing = None;
+        if query.trim().is_empty() || size == 0 {
+            return;
+        }
+        self.entries.retain(|e| e != query);
+        self.entries.push(query.to_string());
+        let excess = self.entries.len().saturating_sub(size);
+        self.entries.drain(..excess);
+        if let Some(path) = &self.path {
+            let _ = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(path, self.entries.join("\n") + "\n"));
+        }
+    }
+    pub fn reset(&mut self) {
+        self.browsing = None;
+    }
+    // the entry before the one shown, remembering what was typed
+    pub fn older(&mut self, query: &str) -> Option<String> {
+        let (idx, draft) = self
+            .browsing
+            .take()
+            .unwrap_or((self.entries.len(), query.to_string()));
+        let idx = idx.checked_sub(1).unwrap_or(idx);
+        self.browsing = Some((idx, draft));
+        self.entries.get(idx).cloned()
+    }
+    // the entry after the one shown, and finally what was typed
+    pub fn newer(&mut self) -> Option<String> {
+        let (idx, draft) = self.browsing.take()?;
+        if idx + 1 < self.entries.len() {
+            self.browsing = Some((idx + 1, draft));
+            self.entries.get(idx + 1).cloned()
+        } else {
+            Some(draft)
+        }
+    }
+    // the next older entry containing what was typed, ignoring case
+    pub fn search(&mut self, query: &str) -> Option<String> {
+        let (idx, draft) = self
+            .browsing
+            .take()
+            .unwrap_or((self.entries.len(), query.to_string()));
+        let needle = draft.to_lowercase();
+        let found = self.entries[..idx]
+            .iter()
+            .rposition(|e| e.to_lowercase().contains(&needle));
+        self.browsing = Some((found.unwrap_or(idx), draft));
+        found.map(|i| self.entries[i].clone())
+    }
+}
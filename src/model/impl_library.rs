@@ -1,7 +1,6 @@
 use super::proto::*;
 use super::*;
-use nucleo_matcher::Matcher;
-use search_utils::{compute_indices, compute_orders};
+use search_utils::with_readings;
 
 impl LibraryState {
     pub fn new() -> Self {
@@ -9,12 +8,15 @@ impl LibraryState {
             artist_search: super::Filter::new(),
             global_search: GlobalSearchState {
                 contents: None,
+                worker: None,
                 results_state: ListState::default(),
                 search: Filter::new(),
             },
             active: super::LibActiveSelector::ArtistSelector,
             contents: Vec::new(),
             artist_state: ListState::default(),
+            db_update: None,
+            loader: None,
         }
     }
 }
@@ -65,7 +67,7 @@ impl Searchable<ArtistData> for LibraryState {
     }
     fn update_filter_cache(
         &mut self,
-        matcher: &mut Matcher,
+        matcher: &mut SearchMatcher,
         top_k: Option<usize>,
     ) {
         if self.filter().cache.query == self.artist_search.query {
@@ -75,34 +77,11 @@ impl Searchable<ArtistData> for LibraryState {
             self.filter_mut().cache.utfstrings_cache = Some(
                 self.contents
                     .iter()
-                    .map(|i| Utf32String::from(i.to_fuzzy_find_str()))
+                    .map(|i| with_readings(i.to_fuzzy_find_str()))
                     .collect(),
             );
         }
-        self.filter_mut().cache.order = compute_orders(
-            &self.filter().query,
-            self.filter().cache.utfstrings_cache.as_ref().unwrap(),
-            matcher,
-            0,
-        );
-
-        let strings_iterator = self
-            .filter()
-            .cache
-            .order
-            .iter()
-            .take_while(|i| i.is_some())
-            .map(|i| {
-                &self.filter().cache.utfstrings_cache.as_ref().unwrap()
-                    [i.unwrap()]
-            });
-        let strings: Vec<&Utf32String>;
-        if let Some(k) = top_k {
-            strings = strings_iterator.take(k).collect();
-        } else {
-            strings = strings_iterator.collect();
-        }
-        self.filter_mut().cache.indices =
-            compute_indices(&self.filter().query, strings, matcher);
+        self.filter_mut().update_order(matcher, 0);
+        self.filter_mut().update_indices(matcher, top_k);
     }
 }
@@ -0,0 +1,109 @@
+use super::proto::*;
+use super::*;
+
+impl Selector for PlaylistSelector {
+    fn selector(&self) -> &impl SelectorState {
+        &self.state
+    }
+    fn selector_mut(&mut self) -> &mut impl SelectorState {
+        &mut self.state
+    }
+    fn len(&self) -> usize {
+        self.contents_vec().len()
+    }
+}
+
+impl Searchable<Playlist> for PlaylistSelector {
+    fn filter(&self) -> &Filter {
+        &self.search
+    }
+    fn filter_mut(&mut self) -> &mut Filter {
+        &mut self.search
+    }
+    fn contents(&self) -> Box<dyn Iterator<Item = &Playlist> + '_> {
+        if self.should_filter() {
+            Box::new(
+                self.filter()
+                    .cache
+                    .order
+                    .iter()
+                    .filter_map(|idx| idx.map(|i| &self.contents[i])),
+            )
+        } else {
+            Box::new(self.contents.iter())
+        }
+    }
+    fn selected_item_mut(&mut self) -> Option<&mut Playlist> {
+        if self.should_filter() {
+            self.selector()
+                .selected()
+                .and_then(|i| self.filter().cache.order.get(i).cloned())
+                .and_then(|i| self.contents.get_mut(i?))
+        } else {
+            self.selector()
+                .selected()
+                .and_then(|i| self.contents.get_mut(i))
+        }
+    }
+    fn update_filter_cache(
+        &mut self,
+        matcher: &mut SearchMatcher,
+        top_k: Option<usize>,
+    ) {
+        if self.filter().cache.query == self.filter().query {
+            return;
+        }
+        if self.filter().cache.utfstrings_cache.is_none() {
+            self.filter_mut().cache.utfstrings_cache = Some(
+                self.contents
+                    .iter()
+                    .map(|i| Utf32String::from(i.name.clone()))
+                    .collect(),
+            );
+        }
+        self.filter_mut().cache.query = self.filter().query.clone();
+        self.filter_mut().update_order(matcher, 0);
+        self.filter_mut().update_indices(matcher, top_k);
+    }
+}
+
+impl Selector for PlaylistPreview {
+    fn selector(&self) -> &impl SelectorState {
+        &self.state
+    }
+    fn selector_mut(&mut self) -> &mut impl SelectorState {
+        &mut self.state
+    }
+    fn len(&self) -> usize {
+        self.tracks.len()
+    }
+}
+
+impl PlaylistSelector {
+    pub fn new() -> Self {
+        Self {
+            search: Filter::new(),
+            contents: Vec::new(),
+            state: ListState::default(),
+            active: PlaylistActiveSelector::PlaylistSelector,
+            preview: PlaylistPreview {
+                name: None,
+                tracks: Vec::new(),
+                state: TableState::default(),
+            },
+        }
+    }
+    pub fn set_contents(&mut self, contents: Vec<Playlist>) {
+        if contents
+            .iter()
+            .map(|p| &p.name)
+            .ne(self.contents.iter().map(|p| &p.name))
+        {
+            self.search.cache.invalidate();
+        }
+        self.contents = contents;
+    }
+    pub fn preview_is_stale(&self) -> bool {
+        self.selected_item().map(|p| &p.name) != self.preview.name.as_ref()
+    }
+}
@@ -0,0 +1,109 @@
+use super::proto::*;
+use super::*;
+
+impl PromptAction {
+    // confirmations are answered with a single y/n key instead of text
+    pub fn is_confirmation(&self) -> bool {
+        matches!(
+            self,
+            PromptAction::OverwritePlaylist(_)
+                | PromptAction::DeletePlaylist(_)
+        )
+    }
+}
+
+impl Popup {
+    pub fn config_errors(config: &Config) -> Option<Self> {
+        if config.errors.is_empty() {
+            return None;
+        }
+        Some(Popup {
+            title: match &config.path {
+                Some(path) => format!("Errors in {}", path.display()),
+                None => "Config errors".to_string(),
+            },
+            lines: config.errors.iter().map(|e| e.to_string()).collect(),
+        })
+    }
+}
+
+impl Completions {
+    pub fn new(contents: Vec<String>) -> Self {
+        let mut search = Filter::new();
+        search.set_on();
+        Self {
+            search,
+            contents,
+            state: ListState::default(),
+        }
+    }
+}
+
+impl Selector for Completions {
+    fn selector(&self) -> &impl SelectorState {
+        &self.state
+    }
+    fn selector_mut(&mut self) -> &mut impl SelectorState {
+        &mut self.state
+    }
+    fn len(&self) -> usize {
+        self.contents_vec().len()
+    }
+}
+
+impl Searchable<String> for Completions {
+    fn filter(&self) -> &Filter {
+        &self.search
+    }
+    fn filter_mut(&mut self) -> &mut Filter {
+        &mut self.search
+    }
+    fn contents(&self) -> Box<dyn Iterator<Item = &String> + '_> {
+        if self.should_filter() {
+            Box::new(
+                self.filter()
+                    .cache
+                    .order
+                    .iter()
+                    .filter_map(|idx| idx.map(|i| &self.contents[i])),
+            )
+        } else if self.filter().query.is_empty() {
+            Box::new(self.contents.iter())
+        } else {
+            // nothing matches: the input names a new entry
+            Box::new(std::iter::empty())
+        }
+    }
+    fn selected_item_mut(&mut self) -> Option<&mut String> {
+        if self.should_filter() {
+            self.selector()
+                .selected()
+                .and_then(|i| self.filter().cache.order.get(i).cloned())
+                .and_then(|i| self.contents.get_mut(i?))
+        } else {
+            self.selector()
+                .selected()
+                .and_then(|i| self.contents.get_mut(i))
+        }
+    }
+    fn update_filter_cache(
+        &mut self,
+        matcher: &mut SearchMatcher,
+        top_k: Option<usize>,
+    ) {
+        if self.filter().cache.query == self.filter().query {
+            return;
+        }
+        if self.filter().cache.utfstrings_cache.is_none() {
+            self.filter_mut().cache.utfstrings_cache = Some(
+                self.contents
+                    .iter()
+                    .map(|i| Utf32String::from(i.as_str()))
+                    .collect(),
+            );
+        }
+        self.filter_mut().cache.query = self.filter().query.clone();
+        self.filter_mut().update_order(matcher, 0);
+        self.filter_mut().update_indices(matcher, top_k);
+    }
+}
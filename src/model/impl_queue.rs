@@ -1,7 +1,6 @@
 use super::proto::*;
 use super::*;
 use crate::util::song_to_str;
-use nucleo_matcher::Matcher;
 
 impl Selector for QueueSelector {
     fn selector(&self) -> &impl SelectorState {
@@ -51,7 +50,7 @@ impl Searchable<Song> for QueueSelector {
     #[allow(unused_variables)]
     fn update_filter_cache(
         &mut self,
-        matcher: &mut Matcher,
+        matcher: &mut SearchMatcher,
         top_k: Option<usize>,
     ) {
         if self.filter().cache.query == self.filter().query {
@@ -65,12 +64,7 @@ impl Searchable<Song> for QueueSelector {
                     .collect(),
             );
         }
-        self.filter_mut().cache.order = search_utils::compute_orders(
-            &self.filter().query,
-            self.filter().cache.utfstrings_cache.as_ref().unwrap(),
-            matcher,
-            0,
-        );
+        self.filter_mut().update_order(matcher, 0);
     }
 }
 
@@ -82,4 +76,10 @@ impl QueueSelector {
             state: TableState::default(),
         }
     }
+    pub fn set_contents(&mut self, contents: Vec<Song>) {
+        if contents != self.contents {
+            self.search.cache.invalidate();
+        }
+        self.contents = contents;
+    }
 }
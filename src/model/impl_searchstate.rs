@@ -1,7 +1,9 @@
 use super::*;
-use nucleo_matcher::Matcher;
+use nucleo_matcher::Utf32Str;
 use proto::*;
 use search_utils::*;
+use std::ops::Range;
+use std::sync::Arc;
 
 impl FilterCache {
     pub fn new() -> Self {
@@ -10,6 +12,7 @@ impl FilterCache {
             order: Vec::new(),
             indices: Vec::new(),
             utfstrings_cache: None,
+            matched: None,
         }
     }
     pub fn clear_matches(&mut self) {
@@ -17,6 +20,21 @@ impl FilterCache {
         self.order.clear();
         self.indices.clear();
     }
+    // for when the strings no longer describe the contents
+    pub fn invalidate(&mut self) {
+        self.utfstrings_cache = None;
+        self.matched = None;
+        self.clear_matches();
+    }
+    // the strings worth scoring for `query`: only the previous matches if
+    // it narrows the last query, all of them otherwise
+    pub fn candidates(&self, query: &str) -> Vec<usize> {
+        match &self.matched {
+            Some((old, matched)) if narrows(old, query) => matched.clone(),
+            _ => (0..self.utfstrings_cache.as_ref().map_or(0, Vec::len))
+                .collect(),
+        }
+    }
 }
 
 impl Filter {
@@ -24,29 +42,78 @@ impl Filter {
         Self {
             active: false,
             query: String::new(),
+            cursor: 0,
             cache: FilterCache::new(),
         }
     }
     pub fn set_on(&mut self) {
         self.active = true;
     }
+    // orders the cached strings by `score`, keeping those above the
+    // threshold. Typing on from the last query only rescores its matches.
+    pub fn rescore(
+        &mut self,
+        threshold: u32,
+        mut score: impl FnMut(usize, &Utf32String) -> Option<u32>,
+    ) {
+        let strings = self.cache.utfstrings_cache.as_ref().unwrap();
+        let scores = self
+            .cache
+            .candidates(&self.query)
+            .into_iter()
+            .map(|i| (i, score(i, &strings[i])))
+            .collect();
+        self.cache.order = order_by_score(scores, strings.len(), threshold);
+        self.cache.matched =
+            Some((self.query.clone(), matched(&self.cache.order)));
+    }
+    pub fn update_order(
+        &mut self,
+        matcher: &mut SearchMatcher,
+        threshold: u32,
+    ) {
+        let pattern = matcher.pattern(&self.query);
+        self.rescore(threshold, |_, s| pattern.score(s.slice(..), matcher));
+    }
+    // where the query matched in the best `top_k` of the ordered strings
+    pub fn update_indices(
+        &mut self,
+        matcher: &mut SearchMatcher,
+        top_k: Option<usize>,
+    ) {
+        let strings = self.cache.utfstrings_cache.as_ref().unwrap();
+        let matched = self
+            .cache
+            .order
+            .iter()
+            .map_while(|i| i.map(|i| &strings[i]))
+            .take(top_k.unwrap_or(usize::MAX))
+            .collect();
+        self.cache.indices = compute_indices(&self.query, matched, matcher);
+    }
     pub fn set_off(&mut self) {
         self.active = false;
         self.query.clear();
+        self.cursor = 0;
         self.cache.clear_matches();
     }
 }
 
+// rows are [albumartist, albumartistsort, album, date, genre, title], cut
+// short at the level they describe
 impl From<&mut Vec<String>> for InfoEntry {
     fn from(v: &mut Vec<String>) -> Self {
-        if v.len() > 4 {
+        if v.len() > 6 {
             panic!("too much info given to infoentry");
         } else {
             let mut drained = v.drain(..);
+            let tag = |s: Option<String>| s.filter(|s| !s.is_empty());
             InfoEntry {
                 artist: drained.nth(0).unwrap(),
                 artist_sort: drained.nth(0),
                 album: drained.nth(0),
+                date: tag(drained.nth(0)),
+                genre: tag(drained.nth(0)),
                 title: drained.nth(0),
             }
         }
@@ -59,6 +126,78 @@ impl InfoEntry {
             && self.title.is_none()
             && self.artist_sort.as_ref().is_some_and(|i| *i == self.artist)
     }
+    // grapheme range of a shown field in `to_search_string`
+    fn span(&self, field: SearchField) -> Option<Range<usize>> {
+        let mut artist = grapheme_len(&self.artist);
+        if let Some(sort) =
+            self.artist_sort.as_ref().filter(|s| **s != self.artist)
+        {
+            artist += grapheme_len(sort) + 3;
+        }
+        let album = self.album.as_deref().map(grapheme_len);
+        let album_start = artist + 1;
+        let title_start = album.map_or(artist, |len| album_start + len) + 1;
+        match field {
+            SearchField::Artist => Some(0..artist),
+            SearchField::Album => {
+                album.map(|len| album_start..album_start + len)
+            }
+            SearchField::Title => self
+                .title
+                .as_ref()
+                .map(|t| title_start..title_start + grapheme_len(t)),
+            SearchField::Genre | SearchField::Date => None,
+        }
+    }
+    pub fn score(
+        &self,
+        s: &Utf32String,
+        query: &FieldQuery,
+        matcher: &mut SearchMatcher,
+    ) -> Option<u32> {
+        let mut score = query.rest.score(s.slice(..), matcher)?;
+        let mut buf = Vec::new();
+        // a missing tag is scored as empty, so that negated terms keep
+        // untagged entries. Fields are scored as written, without the
+        // readings appended to `s`.
+        for (field, pattern) in &query.fields {
+            let haystack = match field {
+                SearchField::Genre => Utf32Str::new(
+                    self.genre.as_deref().unwrap_or_default(),
+                    &mut buf,
+                ),
+                SearchField::Date => Utf32Str::new(
+                    self.date.as_deref().unwrap_or_default(),
+                    &mut buf,
+                ),
+                field => self
+                    .span(*field)
+                    .map_or(Utf32Str::Ascii(&[]), |r| s.slice(r)),
+            };
+            score += pattern.score(haystack, matcher)?;
+        }
+        Some(score)
+    }
+    // positions in `to_search_string`; hidden fields aren't highlighted
+    pub fn indices(
+        &self,
+        s: &Utf32String,
+        query: &FieldQuery,
+        matcher: &mut SearchMatcher,
+    ) -> Vec<u32> {
+        let mut indices = Vec::new();
+        query.rest.indices(s.slice(..), matcher, &mut indices);
+        fold_readings(s.slice(..), &mut indices);
+        for (field, pattern) in &query.fields {
+            if let Some(range) = self.span(*field) {
+                let mut found = Vec::new();
+                pattern.indices(s.slice(range.clone()), matcher, &mut found);
+                indices
+                    .extend(found.into_iter().map(|i| i + range.start as u32));
+            }
+        }
+        indices
+    }
     pub fn to_search_string(&self) -> String {
         let mut out: String = self.artist.clone();
         if let Some(artist_sort) = &self.artist_sort {
@@ -78,6 +217,41 @@ impl InfoEntry {
     }
 }
 
+impl GlobalSearchState {
+    // takes results from the worker, unless the query or the entries have
+    // changed since. Returns whether they were used.
+    pub fn apply_matches(
+        &mut self,
+        matches: Matches,
+        matcher: &mut SearchMatcher,
+        top_k: Option<usize>,
+    ) -> bool {
+        let current = self
+            .contents
+            .as_ref()
+            .is_some_and(|c| Arc::ptr_eq(c, &matches.entries));
+        if !current || !self.search.active || matches.query != self.search.query
+        {
+            return false;
+        }
+        let query = FieldQuery::parse(&matches.query, matcher);
+        let entries = &matches.entries;
+        self.search.cache.indices = matches
+            .order
+            .iter()
+            .map_while(|i| *i)
+            .take(top_k.unwrap_or(usize::MAX))
+            .map(|i| {
+                let s = with_readings(entries[i].to_search_string());
+                entries[i].indices(&s, &query, matcher)
+            })
+            .collect();
+        self.search.cache.order = matches.order;
+        self.watch_oob();
+        true
+    }
+}
+
 impl Selector for GlobalSearchState {
     fn selector(&self) -> &impl SelectorState {
         &self.results_state
@@ -134,52 +308,88 @@ impl Searchable<InfoEntry> for GlobalSearchState {
 
     fn update_filter_cache(
         &mut self,
-        matcher: &mut Matcher,
+        matcher: &mut SearchMatcher,
         top_k: Option<usize>,
     ) {
         if self.search.query == self.search.cache.query {
             return;
         }
-        if self.contents.is_none() {
+        let Some(contents) = &self.contents else {
             self.search.cache.order = Vec::new();
             self.search.cache.indices = Vec::new();
             return;
+        };
+        if let Some(worker) = &self.worker {
+            self.search.cache.query = self.search.query.clone();
+            worker.search(
+                self.search.query.clone(),
+                matcher.smart_case,
+                contents.clone(),
+            );
+            return;
         }
         if self.filter().cache.utfstrings_cache.is_none() {
             self.filter_mut().cache.utfstrings_cache = Some(
-                self.contents
+                contents
                     .iter()
-                    .flatten()
-                    .map(|i| Utf32String::from(i.to_search_string()))
+                    .map(|i| with_readings(i.to_search_string()))
                     .collect(),
             );
         }
 
-        self.filter_mut().cache.query = self.filter().query.clone();
-        self.filter_mut().cache.order = compute_orders(
-            &self.filter().query,
-            self.filter().cache.utfstrings_cache.as_ref().unwrap(),
-            matcher,
-            0,
-        );
-
-        let strings_iterator = self
-            .filter()
+        self.search.cache.query = self.search.query.clone();
+        let query = FieldQuery::parse(&self.search.query, matcher);
+        let entries = self.contents.as_ref().unwrap();
+        self.search
+            .rescore(0, |i, s| entries[i].score(s, &query, matcher));
+        let strings = self.search.cache.utfstrings_cache.as_ref().unwrap();
+        self.search.cache.indices = self
+            .search
             .cache
             .order
             .iter()
-            .take_while(|i| i.is_some())
-            .map(|i| {
-                &self.filter().cache.utfstrings_cache.as_ref().unwrap()
-                    [i.unwrap()]
-            });
-        let strings: Vec<&Utf32String>;
-        if let Some(k) = top_k {
-            strings = strings_iterator.take(k).collect();
-        } else {
-            strings = strings_iterator.collect();
+            .map_while(|i| *i)
+            .take(top_k.unwrap_or(usize::MAX))
+            .map(|i| entries[i].indices(&strings[i], &query, matcher))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str) -> InfoEntry {
+        InfoEntry {
+            artist: "Yann Tiersen".to_string(),
+            artist_sort: None,
+            album: Some("Ame\u{301}lie".to_string()),
+            title: Some(title.to_string()),
+            genre: None,
+            date: None,
+        }
+    }
+
+    #[test]
+    fn fields_after_combining_marks_are_in_range() {
+        let mut matcher = SearchMatcher::new(false);
+        let e = entry("La Valse d'Ame\u{301}lie");
+        let s = Utf32String::from(e.to_search_string());
+        for query in ["title:valse", "album:amelie", "artist:tiersen"] {
+            let query = FieldQuery::parse(query, &matcher);
+            assert!(e.score(&s, &query, &mut matcher).is_some());
+            assert!(!e.indices(&s, &query, &mut matcher).is_empty());
         }
-        self.filter_mut().cache.indices =
-            compute_indices(&self.filter().query, strings, matcher);
+    }
+
+    #[test]
+    fn field_indices_land_on_the_field() {
+        let mut matcher = SearchMatcher::new(false);
+        let e = entry("Ame\u{301}lie");
+        let s = Utf32String::from(e.to_search_string());
+        let query = FieldQuery::parse("title:lie", &matcher);
+        let title_start = s.len() - grapheme_len("Ame\u{301}lie");
+        let indices = e.indices(&s, &query, &mut matcher);
+        assert!(indices.iter().all(|i| *i as usize >= title_start));
     }
 }
@@ -0,0 +1,266 @@
+use super::*;
+use crate::connection::{connect, Server};
+use crate::event_handler::{Event, Result};
+use crate::update::build_library::{fetch_artists, fetch_entries};
+use std::fs;
+use std::iter;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+// changed along with the layout, so that older files are refetched
+const HEADER: &str = "inori library 1";
+
+// the library and the global search entries as last fetched from a
+// server, kept on disk so that starting up doesn't wait for them
+pub struct LibraryCache {
+    pub server: Server,
+    // where the server was reached, which names the file
+    pub url: String,
+    // mpd's db_update when these were fetched
+    pub db_update: u64,
+    pub artists: Vec<ArtistData>,
+    pub entries: Vec<InfoEntry>,
+}
+
+impl LibraryCache {
+    pub fn fetch(server: Server) -> Result<Self> {
+        let (mut client, url) = connect(&server)?;
+        Ok(LibraryCache {
+            url,
+            db_update: client.stats()?.db_update.as_secs(),
+            artists: fetch_artists(&mut client)?,
+            entries: fetch_entries(&mut client)?,
+            server,
+        })
+    }
+    // without a usable file, the library is fetched from the server
+    pub fn load(server: &Server, url: &str) -> Option<Self> {
+        let text = fs::read_to_string(path(url)?).ok()?;
+        let (db_update, artists, entries) = parse(&text)?;
+        Some(LibraryCache {
+            server: server.clone(),
+            url: url.to_string(),
+            db_update,
+            artists,
+            entries,
+        })
+    }
+    pub fn save(&self) {
+        save(&self.url, self.db_update, &self.artists, &self.entries);
+    }
+}
+
+// written next to the old file first, so that it is never read half done
+pub fn save(
+    url: &str,
+    db_update: u64,
+    artists: &[ArtistData],
+    entries: &[InfoEntry],
+) {
+    let Some(path) = path(url) else {
+        return;
+    };
+    let mut tmp = path.clone().into_os_string();
+    tmp.push(".tmp");
+    let _ = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&tmp, to_text(db_update, artists, entries)))
+        .and_then(|_| fs::rename(&tmp, &path));
+}
+
+// the other way round from `to_text`. Anything off throws the whole
+// file away, to be fetched again
+fn parse(text: &str) -> Option<(u64, Vec<ArtistData>, Vec<InfoEntry>)> {
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return None;
+    }
+    let db_update = lines.next()?.parse().ok()?;
+    let mut artists = Vec::new();
+    let mut entries = Vec::new();
+    for line in lines {
+        let (kind, rest) = line.split_once('\t')?;
+        let mut fields = rest.split('\t').map(unescape);
+        match kind {
+            "a" => artists.push(ArtistData::from_names(
+                fields.next()??,
+                fields.collect::<Option<_>>()?,
+            )),
+            "e" => entries.push(InfoEntry {
+                artist: fields.next()??,
+                artist_sort: fields.next()?,
+                album: fields.next()?,
+                title: fields.next()?,
+                genre: fields.next()?,
+                date: fields.next()?,
+            }),
+            _ => return None,
+        }
+    }
+    Some((db_update, artists, entries))
+}
+
+fn to_text(
+    db_update: u64,
+    artists: &[ArtistData],
+    entries: &[InfoEntry],
+) -> String {
+    let mut out = format!("{}\n{}\n", HEADER, db_update);
+    for a in artists {
+        let names = iter::once(&a.name).chain(&a.sort_names).map(Some);
+        push_line(&mut out, "a", names);
+    }
+    for e in entries {
+        let fields = [
+            Some(&e.artist),
+            e.artist_sort.as_ref(),
+            e.album.as_ref(),
+            e.title.as_ref(),
+            e.genre.as_ref(),
+            e.date.as_ref(),
+        ];
+        push_line(&mut out, "e", fields.into_iter());
+    }
+    out
+}
+
+// one file per address the server was reached at
+fn path(url: &str) -> Option<PathBuf> {
+    let name: String = url
+        .chars()
+        .map(|c| match c.is_alphanumeric() || c == '.' || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    dirs::cache_dir().map(|mut p| {
+        p.push(PathBuf::from_iter(["inori", &format!("library-{}", name)]));
+        p
+    })
+}
+
+fn push_line<'a>(
+    out: &mut String,
+    kind: &str,
+    fields: impl Iterator<Item = Option<&'a String>>,
+) {
+    out.push_str(kind);
+    for f in fields {
+        out.push('\t');
+        out.push_str(&escape(f));
+    }
+    out.push('\n');
+}
+
+// tags can hold anything, so the separators are escaped, and a missing
+// tag is written as `\0`
+fn escape(s: Option<&String>) -> String {
+    match s {
+        None => "\\0".to_string(),
+        Some(s) => s
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r"),
+    }
+}
+
+fn unescape(s: &str) -> Option<String> {
+    if s == "\\0" {
+        return None;
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        out.push(match c {
+            '\\' => match chars.next() {
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some(c) => c,
+                None => break,
+            },
+            c => c,
+        });
+    }
+    Some(out)
+}
+
+// fetches the library on a connection of its own, and sends it back once
+// it has been saved
+pub struct LibraryLoader {
+    events: Sender<Event>,
+}
+
+impl LibraryLoader {
+    pub fn new(events: Sender<Event>) -> Self {
+        LibraryLoader { events }
+    }
+    pub fn refresh(&self, server: Server) {
+        let events = self.events.clone();
+        thread::spawn(move || {
+            if let Ok(cache) = LibraryCache::fetch(server) {
+                cache.save();
+                let _ = events.send(Event::Library(Box::new(cache)));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: Option<&str>) -> Option<String> {
+        unescape(&escape(s.map(str::to_string).as_ref()))
+    }
+
+    #[test]
+    fn missing_tags_round_trip() {
+        assert_eq!(round_trip(None), None);
+        assert_eq!(round_trip(Some("")), Some(String::new()));
+    }
+
+    #[test]
+    fn separators_round_trip() {
+        for s in ["a\tb", "a\nb\r\n", "C:\\Music\\", "\\t", "\\\\n", "\t\\"] {
+            assert_eq!(round_trip(Some(s)).as_deref(), Some(s));
+        }
+    }
+
+    #[test]
+    fn literal_null_is_not_missing() {
+        assert_eq!(round_trip(Some("\\0")).as_deref(), Some("\\0"));
+        assert_eq!(round_trip(Some("\\")).as_deref(), Some("\\"));
+    }
+
+    #[test]
+    fn escaped_fields_have_no_separators() {
+        let s = escape(Some(&"a\tb\nc".to_string()));
+        assert!(!s.contains(['\t', '\n']));
+    }
+
+    #[test]
+    fn library_round_trips() {
+        let artists = vec![ArtistData::from_names(
+            "A\tB".to_string(),
+            vec!["\\0".to_string(), "b\nc\\".to_string()],
+        )];
+        let entries = vec![InfoEntry {
+            artist: "A\tB".to_string(),
+            artist_sort: None,
+            album: Some("\\0".to_string()),
+            title: Some(String::new()),
+            genre: Some("x\\".to_string()),
+            date: None,
+        }];
+        let (db_update, parsed_artists, parsed_entries) =
+            parse(&to_text(42, &artists, &entries)).unwrap();
+        assert_eq!(db_update, 42);
+        assert_eq!(parsed_artists.len(), 1);
+        assert_eq!(parsed_artists[0].name, artists[0].name);
+        assert_eq!(parsed_artists[0].sort_names, artists[0].sort_names);
+        assert!(parsed_entries == entries);
+    }
+}
@@ -4,6 +4,8 @@ use super::*;
 pub trait SelectorState {
     fn selected(&self) -> Option<usize>;
     fn set_selected(&mut self, s: Option<usize>);
+    // index of the first visible row
+    fn offset(&self) -> usize;
 }
 
 impl SelectorState for ListState {
@@ -13,6 +15,9 @@ impl SelectorState for ListState {
     fn set_selected(&mut self, s: Option<usize>) {
         *self.selected_mut() = s;
     }
+    fn offset(&self) -> usize {
+        self.offset()
+    }
 }
 
 impl SelectorState for TableState {
@@ -22,6 +27,9 @@ impl SelectorState for TableState {
     fn set_selected(&mut self, s: Option<usize>) {
         *self.selected_mut() = s;
     }
+    fn offset(&self) -> usize {
+        self.offset()
+    }
 }
 
 pub trait Selector {
@@ -35,6 +43,9 @@ pub trait Selector {
     fn set_selected(&mut self, val: Option<usize>) {
         self.selector_mut().set_selected(val);
     }
+    fn offset(&self) -> usize {
+        self.selector().offset()
+    }
     fn init(&mut self) {
         // idempotent
         if self.len() != 0 && self.selected().is_none() {
@@ -55,7 +66,7 @@ pub trait Searchable<T>: Selector {
     fn selected_item_mut(&mut self) -> Option<&mut T>;
     fn update_filter_cache(
         &mut self,
-        matcher: &mut Matcher,
+        matcher: &mut SearchMatcher,
         top_k: Option<usize>,
     );
     fn selected_item(&self) -> Option<&T> {
@@ -72,3 +83,11 @@ pub trait Searchable<T>: Selector {
             && !self.filter().query.is_empty()
     }
 }
+
+// selectors that show search matches in place (by rank) instead of
+// filtering their contents, like the track selector.
+pub trait RankedSelector: Selector {
+    fn search_mut(&mut self) -> &mut Filter;
+    fn ranks(&self) -> Vec<Option<usize>>;
+    fn update_search(&mut self, matcher: &mut SearchMatcher);
+}
@@ -0,0 +1,69 @@
+use super::search_utils::grapheme_len;
+use kakasi::IsJapanese;
+use pinyin::ToPinyin;
+
+pub struct Readings {
+    pub text: String,
+    // for each grapheme of `text`, the char of the source it was read from
+    pub source: Vec<u32>,
+}
+
+impl Readings {
+    fn push(&mut self, reading: &str, from: usize, len: usize) {
+        if !self.text.is_empty() {
+            self.text.push(' ');
+            self.source.push(from as u32);
+        }
+        let count = grapheme_len(reading);
+        self.text.push_str(reading);
+        // a reading of several chars spreads evenly over them
+        self.source
+            .extend((0..count).map(|i| (from + i * len / count) as u32));
+    }
+}
+
+// kana and romaji for japanese, pinyin for chinese. Kanji on their own
+// could be either, so they get both.
+// `s` holds one char per grapheme, so that positions match nucleo's.
+pub fn readings(s: &str) -> Option<Readings> {
+    let mut out = Readings {
+        text: String::new(),
+        source: Vec::new(),
+    };
+    let japanese = kakasi::is_japanese(s);
+    if japanese != IsJapanese::False {
+        for (from, run) in cjk_runs(s) {
+            let len = run.chars().count();
+            let read = kakasi::convert(&run);
+            if read.hiragana != run {
+                out.push(&read.hiragana, from, len);
+            }
+            out.push(&read.romaji, from, len);
+        }
+    }
+    if japanese == IsJapanese::Maybe {
+        for (i, c) in s.chars().enumerate() {
+            if let Some(p) = c.to_pinyin() {
+                out.push(p.plain(), i, 1);
+            }
+        }
+    }
+    Some(out).filter(|r| !r.text.is_empty())
+}
+
+// the non-ascii words of `s`, with the index of their first char
+fn cjk_runs(s: &str) -> Vec<(usize, String)> {
+    let mut runs: Vec<(usize, String)> = Vec::new();
+    let mut last = None;
+    for (i, c) in s.chars().enumerate() {
+        if c.is_ascii() || c.is_whitespace() {
+            continue;
+        }
+        match runs.last_mut() {
+            Some((_, run)) if last == Some(i - 1) => run.push(c),
+            _ => runs.push((i, c.to_string())),
+        }
+        last = Some(i);
+    }
+    runs
+}
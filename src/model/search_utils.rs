@@ -1,57 +1,247 @@
-use nucleo_matcher::pattern::{AtomKind, CaseMatching, Normalization, Pattern};
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
 use nucleo_matcher::Matcher;
-use nucleo_matcher::Utf32String;
+use nucleo_matcher::{Utf32Str, Utf32String};
+use std::ops::{Deref, DerefMut};
 
-pub fn compute_orders(
-    query: &str,
-    strings: &[Utf32String],
-    matcher: &mut Matcher,
+// the matcher together with how queries are parsed for it
+pub struct SearchMatcher {
+    matcher: Matcher,
+    pub smart_case: bool,
+}
+
+impl SearchMatcher {
+    pub fn new(smart_case: bool) -> Self {
+        let mut config = nucleo_matcher::Config::DEFAULT;
+        config.prefer_prefix = true;
+        SearchMatcher {
+            matcher: Matcher::new(config),
+            smart_case,
+        }
+    }
+    // fzf syntax: 'exact, ^prefix, suffix$ and !negated atoms
+    pub fn pattern(&self, query: &str) -> Pattern {
+        let case = match self.smart_case {
+            true => CaseMatching::Smart,
+            false => CaseMatching::Ignore,
+        };
+        Pattern::parse(query, case, Normalization::Smart)
+    }
+}
+
+impl Deref for SearchMatcher {
+    type Target = Matcher;
+    fn deref(&self) -> &Matcher {
+        &self.matcher
+    }
+}
+
+impl DerefMut for SearchMatcher {
+    fn deref_mut(&mut self) -> &mut Matcher {
+        &mut self.matcher
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SearchField {
+    Artist,
+    Album,
+    Title,
+    Genre,
+    Date,
+}
+
+// a global search query, with the `field:word` terms split from the ones
+// matched against the whole entry
+pub struct FieldQuery {
+    pub rest: Pattern,
+    pub fields: Vec<(SearchField, Pattern)>,
+}
+
+impl FieldQuery {
+    pub fn parse(query: &str, matcher: &SearchMatcher) -> Self {
+        let mut rest: Vec<&str> = Vec::new();
+        let mut fields = Vec::new();
+        for word in query.split_whitespace() {
+            let field = word.split_once(':').and_then(|(f, w)| {
+                let field = match f {
+                    "artist" => SearchField::Artist,
+                    "album" => SearchField::Album,
+                    "title" => SearchField::Title,
+                    "genre" => SearchField::Genre,
+                    "date" => SearchField::Date,
+                    _ => return None,
+                };
+                Some((field, w)).filter(|_| !w.is_empty())
+            });
+            match field {
+                Some((field, w)) => fields.push((field, matcher.pattern(w))),
+                None => rest.push(word),
+            }
+        }
+        FieldQuery {
+            rest: matcher.pattern(&rest.join(" ")),
+            fields,
+        }
+    }
+}
+
+// readings of CJK text are appended after this, so that typing them finds
+// the original
+#[cfg(feature = "cjk")]
+const READINGS_SEP: char = '\u{1f}';
+
+// the readings are taken from the first char of each grapheme, which is
+// all nucleo keeps, so that fold_readings can work them out again
+#[cfg(feature = "cjk")]
+pub fn with_readings(s: String) -> Utf32String {
+    let kept: String = nucleo_matcher::chars::graphemes(&s).collect();
+    match super::reading::readings(&kept) {
+        Some(r) => {
+            Utf32String::from(format!("{}{}{}", s, READINGS_SEP, r.text))
+        }
+        None => Utf32String::from(s),
+    }
+}
+
+#[cfg(not(feature = "cjk"))]
+pub fn with_readings(s: String) -> Utf32String {
+    Utf32String::from(s)
+}
+
+// points matches within the readings at the characters they were read from
+#[cfg(feature = "cjk")]
+pub fn fold_readings(s: Utf32Str, indices: &mut Vec<u32>) {
+    let Some(len) = s.chars().position(|c| c == READINGS_SEP) else {
+        return;
+    };
+    let source: String = s.chars().take(len).collect();
+    let Some(r) = super::reading::readings(&source) else {
+        return;
+    };
+    let len = len as u32;
+    for i in indices.iter_mut().filter(|i| **i > len) {
+        *i = r.source[(*i - len - 1) as usize];
+    }
+    indices.retain(|i| *i != len);
+    indices.sort_unstable();
+    indices.dedup();
+}
+
+#[cfg(not(feature = "cjk"))]
+pub fn fold_readings(_: Utf32Str, _: &mut Vec<u32>) {}
+
+// length of `s` as nucleo sees it, which keeps one char per grapheme
+pub fn grapheme_len(s: &str) -> usize {
+    nucleo_matcher::chars::graphemes(s).count()
+}
+
+// whether everything matching `query` also matched `old`, so that only
+// the old matches need scoring again. Some queries match more as they
+// grow: negated atoms exclude less, and an escape, a `$` anchor or a
+// `field:` prefix changes the meaning of what came before it.
+pub fn narrows(old: &str, query: &str) -> bool {
+    let Some(added) = query.strip_prefix(old) else {
+        return false;
+    };
+    !old.trim().is_empty()
+        && !added.is_empty()
+        && !old.contains('!')
+        && !old.ends_with(['\\', '$', ':'])
+        && !added.contains(':')
+}
+
+// indices sorted by descending score, with those at or below the
+// threshold left out. Scores are paired with the index they are for,
+// out of `len`, and ties keep that order.
+pub fn order_by_score(
+    scores: Vec<(usize, Option<u32>)>,
+    len: usize,
     score_threshold: u32,
 ) -> Vec<Option<usize>> {
-    let pattern = Pattern::new(
-        query,
-        CaseMatching::Ignore,
-        Normalization::Smart,
-        AtomKind::Fuzzy,
-    );
-    let scores = strings
-        .iter()
-        .map(|i| pattern.score(i.slice(..), matcher))
-        .collect::<Vec<Option<u32>>>();
     let mut order = scores
         .into_iter()
-        .enumerate()
+        .filter(|i| i.1.is_some_and(|score| score > score_threshold))
         .collect::<Vec<(usize, Option<u32>)>>();
-    order.sort_by(|a, b| b.1.unwrap_or(0).cmp(&a.1.unwrap_or(0)));
-    let order = order
-        .iter()
-        .map(|i| {
-            if i.1.is_some_and(|score| score > score_threshold) {
-                Some(i.0)
-            } else {
-                None
-            }
-        })
+    order.sort_by_key(|i| std::cmp::Reverse(i.1));
+    let mut order = order
+        .into_iter()
+        .map(|i| Some(i.0))
         .collect::<Vec<Option<usize>>>();
+    order.resize(len, None);
     order
 }
 
+// the indices kept in an order, ascending
+pub fn matched(order: &[Option<usize>]) -> Vec<usize> {
+    let mut matched: Vec<usize> = order.iter().map_while(|i| *i).collect();
+    matched.sort_unstable();
+    matched
+}
+
 pub fn compute_indices(
     query: &str,
     strings: Vec<&Utf32String>,
-    matcher: &mut Matcher,
+    matcher: &mut SearchMatcher,
 ) -> Vec<Vec<u32>> {
-    let pattern = Pattern::new(
-        query,
-        CaseMatching::Ignore,
-        Normalization::Smart,
-        AtomKind::Fuzzy,
-    );
+    let pattern = matcher.pattern(query);
     let mut indices: Vec<Vec<u32>> = Vec::new();
     for s in strings {
         let mut tmp: Vec<u32> = Vec::new();
         pattern.indices(s.slice(..), matcher, &mut tmp);
+        fold_readings(s.slice(..), &mut tmp);
         indices.push(tmp);
     }
     indices
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrows_longer_queries() {
+        assert!(narrows("foo", "foob"));
+        assert!(narrows("foo", "foo bar"));
+        assert!(narrows("fo", "foo$"));
+        assert!(narrows("album:li", "album:live"));
+    }
+
+    #[test]
+    fn needs_something_on_both_sides() {
+        assert!(!narrows("", "foo"));
+        assert!(!narrows("  ", "  foo"));
+        assert!(!narrows("foo", "foo"));
+        assert!(!narrows("foo", "fo"));
+        assert!(!narrows("foo", "bar foo"));
+    }
+
+    #[test]
+    fn negated_atoms_exclude_less() {
+        assert!(!narrows("!foo", "!foob"));
+        assert!(!narrows("bar !foo", "bar !foo baz"));
+    }
+
+    #[test]
+    fn anchors_and_escapes_change_what_came_before() {
+        assert!(!narrows("foo$", "foo$b"));
+        assert!(!narrows("foo\\", "foo\\ bar"));
+    }
+
+    #[test]
+    fn field_prefixes_change_what_came_before() {
+        assert!(!narrows("album", "album:live"));
+        assert!(!narrows("album:", "album:live"));
+        assert!(!narrows("live", "live date:1973"));
+    }
+
+    #[cfg(feature = "cjk")]
+    #[test]
+    fn readings_fold_onto_graphemes() {
+        let mut matcher = SearchMatcher::new(false);
+        // a decomposed が, one grapheme of two chars
+        let s = with_readings("か\u{3099}っこう".to_string());
+        let indices = compute_indices("kakkou", vec![&s], &mut matcher);
+        assert!(!indices[0].is_empty());
+        assert!(indices[0].iter().all(|i| *i < 4));
+    }
+}
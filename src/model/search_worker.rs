@@ -0,0 +1,143 @@
+use super::search_utils::*;
+use super::*;
+use crate::event_handler::Event;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// entries scored between checks for a newer query
+const CHUNK: usize = 4096;
+// how often partial results are sent while a query runs
+const STREAM_INTERVAL: Duration = Duration::from_millis(50);
+
+// scores global search queries off the interface thread. Only the latest
+// query is worked on, and results are sent back as events while it runs.
+pub struct SearchWorker {
+    jobs: Sender<Job>,
+}
+
+struct Job {
+    query: String,
+    smart_case: bool,
+    entries: Arc<Vec<InfoEntry>>,
+}
+
+// the best matches found so far for `query` among `entries`
+pub struct Matches {
+    pub query: String,
+    pub entries: Arc<Vec<InfoEntry>>,
+    pub order: Vec<Option<usize>>,
+}
+
+impl SearchWorker {
+    pub fn new(events: Sender<Event>) -> Self {
+        let (jobs, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut worker = Worker {
+                matcher: SearchMatcher::new(false),
+                entries: None,
+                strings: Vec::new(),
+                matched: None,
+            };
+            let mut next = rx.recv().ok();
+            while let Some(job) = next {
+                next = worker.run(job, &rx, &events);
+            }
+        });
+        SearchWorker { jobs }
+    }
+    pub fn search(
+        &self,
+        query: String,
+        smart_case: bool,
+        entries: Arc<Vec<InfoEntry>>,
+    ) {
+        let _ = self.jobs.send(Job {
+            query,
+            smart_case,
+            entries,
+        });
+    }
+}
+
+struct Worker {
+    matcher: SearchMatcher,
+    // the entries `strings` were made from
+    entries: Option<Arc<Vec<InfoEntry>>>,
+    strings: Vec<Utf32String>,
+    // the last query that ran to the end and the indices that matched it
+    matched: Option<(String, Vec<usize>)>,
+}
+
+impl Worker {
+    // returns the next job, which may cut this one short. None once the
+    // interface is gone.
+    fn run(
+        &mut self,
+        job: Job,
+        rx: &Receiver<Job>,
+        events: &Sender<Event>,
+    ) -> Option<Job> {
+        if !self
+            .entries
+            .as_ref()
+            .is_some_and(|e| Arc::ptr_eq(e, &job.entries))
+        {
+            self.strings = job
+                .entries
+                .iter()
+                .map(|i| with_readings(i.to_search_string()))
+                .collect();
+            self.entries = Some(job.entries.clone());
+            self.matched = None;
+        }
+        if self.matcher.smart_case != job.smart_case {
+            self.matcher.smart_case = job.smart_case;
+            self.matched = None;
+        }
+        let len = job.entries.len();
+        let candidates = match &self.matched {
+            Some((old, matched)) if narrows(old, &job.query) => matched.clone(),
+            _ => (0..len).collect(),
+        };
+        let query = FieldQuery::parse(&job.query, &self.matcher);
+        let mut scores = Vec::with_capacity(candidates.len());
+        let mut sent = Instant::now();
+        for chunk in candidates.chunks(CHUNK) {
+            if let Some(newer) = rx.try_iter().last() {
+                return Some(newer);
+            }
+            scores.extend(chunk.iter().map(|&i| {
+                let score = job.entries[i].score(
+                    &self.strings[i],
+                    &query,
+                    &mut self.matcher,
+                );
+                (i, score)
+            }));
+            if sent.elapsed() >= STREAM_INTERVAL {
+                send(events, &job, order_by_score(scores.clone(), len, 0))?;
+                sent = Instant::now();
+            }
+        }
+        let order = order_by_score(scores, len, 0);
+        self.matched = Some((job.query.clone(), matched(&order)));
+        send(events, &job, order)?;
+        rx.recv().ok()
+    }
+}
+
+fn send(
+    events: &Sender<Event>,
+    job: &Job,
+    order: Vec<Option<usize>>,
+) -> Option<()> {
+    events
+        .send(Event::Matches(Matches {
+            query: job.query.clone(),
+            entries: job.entries.clone(),
+            order,
+        }))
+        .ok()
+}
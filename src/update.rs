@@ -1,11 +1,14 @@
 use crate::config::keybind::{KeybindMap, KeybindTarget};
-use crate::event_handler::Result;
+use crate::connection::{
+    is_connection_error, is_permission_error, permission_message,
+};
+use crate::event_handler::{Event, Result};
 use crate::model::proto::Searchable;
-use crate::model::{Model, Screen, State};
+use crate::model::{Model, PromptAction, Screen, State};
 use crate::util::{safe_decrement, safe_increment};
 use bitflags::bitflags;
 use mpd::status::State as PlayState;
-use ratatui::crossterm::event::{self, KeyCode, KeyEvent};
+use ratatui::crossterm::event::{self, KeyCode, KeyEvent, KeyModifiers};
 use std::option::Option;
 use std::time::Duration;
 
@@ -20,6 +23,8 @@ bitflags! {
         const STATUS = 0b00000100;
         const CURRENT_SONG = 0b00001000;
         const START_PLAYING = 0b00010000;
+        const PLAYLISTS = 0b00100000;
+        const DATABASE = 0b01000000;
     }
 }
 
@@ -49,6 +54,14 @@ pub enum SearchMsg {
     End,
 }
 
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum PlaylistMsg {
+    Load,
+    Append,
+    Rename,
+    Delete,
+}
+
 #[derive(Clone, Debug)]
 pub enum Toggle {
     Repeat,
@@ -63,6 +76,14 @@ pub enum SeekDirection {
     Backward,
 }
 
+// a search query is edited one key at a time, or all at once by pasting
+pub enum SearchInput {
+    Key(KeyEvent),
+    Paste(String),
+    // replaces the query with one from the history
+    Recall(String),
+}
+
 #[derive(Clone, Debug)]
 pub enum Message {
     Direction(Dirs),
@@ -78,20 +99,106 @@ pub enum Message {
     TogglePanel,
     Fold,
     Clear,
+    SaveQueue,
     LocalSearch(SearchMsg),
     GlobalSearch(SearchMsg),
     Escape,
     Set(Toggle),
+    Playlist(PlaylistMsg),
+    AddToPlaylist,
+    SwitchServer,
+    ReloadConfig,
 }
 
+// everything else is pushed by the idle connection; ticks only keep the
+// elapsed time current, retry the connection while it is down and watch
+// the config file. The elapsed time only moves while playing, and is left
+// alone while the terminal is unfocused.
 pub fn update_tick(model: &mut Model) -> Result<()> {
-    update_screens(model, Update::all())?;
+    if model.config.watch_config && model.config.is_stale() {
+        model.reload_config();
+    }
+    if !model.conn.is_connected() {
+        if model.conn.try_reconnect() {
+            update_screens(model, Update::all())?;
+        }
+        return Ok(());
+    }
+    if model.focused && model.status.state == PlayState::Play {
+        update_screens(model, Update::STATUS)?;
+    }
     Ok(())
 }
 
+pub fn update_event(model: &mut Model, event: Event) -> Result<()> {
+    let res = match event {
+        Event::Tick => update_tick(model),
+        Event::Key(k) => {
+            model.message = None;
+            handle_key(model, k).and_then(|u| update_screens(model, u))
+        }
+        Event::Mouse(m) => handlers::mouse_handler::handle_mouse(model, m)
+            .and_then(|u| update_screens(model, u)),
+        Event::Paste(text) => {
+            model.message = None;
+            handle_paste(model, text).and_then(|u| update_screens(model, u))
+        }
+        Event::Focus(focused) => {
+            model.focused = focused;
+            match focused {
+                true => {
+                    update_screens(model, Update::STATUS | Update::CURRENT_SONG)
+                }
+                false => Ok(()),
+            }
+        }
+        Event::Player => {
+            update_screens(model, Update::STATUS | Update::CURRENT_SONG)
+        }
+        Event::Queue => update_screens(model, Update::QUEUE | Update::STATUS),
+        Event::Database => update_screens(model, Update::DATABASE),
+        Event::Options | Event::Mixer => update_screens(model, Update::STATUS),
+        Event::StoredPlaylist => update_screens(model, Update::PLAYLISTS),
+        Event::Library(cache) => {
+            build_library::apply_library(model, *cache);
+            update_screens(model, Update::empty())
+        }
+        Event::Matches(m) => {
+            handlers::library_handler::handle_matches(model, m)
+                .and_then(|u| update_screens(model, u))
+        }
+    };
+    recover(model, res)
+}
+
+pub fn refresh_all(model: &mut Model) -> Result<()> {
+    let res = update_screens(model, Update::all());
+    recover(model, res)
+}
+
+// a dropped connection is not fatal: note it and let the tick reconnect.
+// Refused commands are reported to the user instead of crashing.
+fn recover(model: &mut Model, res: Result<()>) -> Result<()> {
+    match res {
+        Err(e) if is_connection_error(e.as_ref()) => {
+            model.conn.disconnect();
+            Ok(())
+        }
+        Err(e) if is_permission_error(e.as_ref()) => {
+            model.message = Some(permission_message(e.as_ref()));
+            Ok(())
+        }
+        other => other,
+    }
+}
+
 pub fn update_screens(model: &mut Model, update: Update) -> Result<()> {
+    if update.contains(Update::DATABASE) {
+        build_library::rebuild_library(model)?;
+    }
     if update.contains(Update::QUEUE) {
-        model.queue.contents = model.conn.queue().unwrap_or_default();
+        let queue = model.conn.client()?.queue().unwrap_or_default();
+        model.queue.set_contents(queue);
     }
     if update.contains(Update::CURRENT_ARTIST)
         && model.library.selected_item_mut().is_some()
@@ -100,12 +207,13 @@ pub fn update_screens(model: &mut Model, update: Update) -> Result<()> {
     }
     if update.contains(Update::START_PLAYING) {
         if !update.contains(Update::QUEUE) {
-            model.queue.contents = model.conn.queue().unwrap_or_default();
+            let queue = model.conn.client()?.queue().unwrap_or_default();
+            model.queue.set_contents(queue);
         }
         model.update_status()?;
         if model.status.queue_len > 0 && model.status.state == mpd::State::Stop
         {
-            model.conn.switch(0)?;
+            model.conn.client()?.switch(0)?;
         }
     }
     if update.contains(Update::CURRENT_SONG) {
@@ -114,9 +222,16 @@ pub fn update_screens(model: &mut Model, update: Update) -> Result<()> {
     if update.contains(Update::STATUS) {
         model.update_status()?;
     }
+    if update.contains(Update::PLAYLISTS)
+        && matches!(model.screen, Screen::Playlists)
+    {
+        model.update_playlists()?;
+    }
     match model.screen {
         Screen::Library => updaters::update_library(model)?,
         Screen::Queue => updaters::update_queue(model)?,
+        Screen::Playlists => updaters::update_playlists(model)?,
+        Screen::Directories => updaters::update_directories(model)?,
     }
     Ok(())
 }
@@ -141,15 +256,12 @@ fn parse_msg(
 }
 
 pub fn handle_key(model: &mut Model, k: KeyEvent) -> Result<Update> {
+    if model.popup.take().is_some() {
+        return Ok(Update::empty());
+    }
     match model.state {
-        State::Searching => match model.screen {
-            Screen::Library => {
-                Ok(handlers::library_handler::handle_search(model, k)?)
-            }
-            Screen::Queue => {
-                Ok(handlers::queue_handler::handle_search(model, k)?)
-            }
-        },
+        State::Searching => handle_search(model, SearchInput::Key(k)),
+        State::Prompting => handlers::prompt_handler::handle_prompt(model, k),
         State::Running => {
             if let Some(m) =
                 parse_msg(k, &mut model.parse_state, &model.config.keybindings)
@@ -163,6 +275,71 @@ pub fn handle_key(model: &mut Model, k: KeyEvent) -> Result<Update> {
     }
 }
 
+// pasted text is only meaningful where something is being typed
+pub fn handle_paste(model: &mut Model, text: String) -> Result<Update> {
+    if model.popup.take().is_some() {
+        return Ok(Update::empty());
+    }
+    match model.state {
+        State::Searching => handle_search(model, SearchInput::Paste(text)),
+        State::Prompting => {
+            handlers::prompt_handler::paste_prompt(model, &text);
+            Ok(Update::empty())
+        }
+        State::Running | State::Done => Ok(Update::empty()),
+    }
+}
+
+fn handle_search(model: &mut Model, input: SearchInput) -> Result<Update> {
+    let input = match input {
+        SearchInput::Key(k) => recall_history(model, k),
+        other => {
+            model.history.reset();
+            other
+        }
+    };
+    match model.screen {
+        Screen::Library => {
+            handlers::library_handler::handle_search(model, input)
+        }
+        Screen::Queue => handlers::queue_handler::handle_search(model, input),
+        Screen::Playlists => {
+            handlers::playlist_handler::handle_search(model, input)
+        }
+        Screen::Directories => {
+            handlers::directory_handler::handle_search(model, input)
+        }
+    }
+}
+
+// up and down go through earlier searches, and C-r goes back to the
+// last one containing what was typed. Enter submits the query.
+fn recall_history(model: &mut Model, k: KeyEvent) -> SearchInput {
+    let query = model
+        .active_filter()
+        .map(|f| f.query.clone())
+        .unwrap_or_default();
+    let recalled = match k.code {
+        KeyCode::Up => model.history.older(&query),
+        KeyCode::Down => model.history.newer(),
+        KeyCode::Char('r') if k.modifiers.contains(KeyModifiers::CONTROL) => {
+            model.history.search(&query)
+        }
+        KeyCode::Enter => {
+            model.history.push(&query, model.config.history_size);
+            None
+        }
+        _ => {
+            model.history.reset();
+            None
+        }
+    };
+    match recalled {
+        Some(q) => SearchInput::Recall(q),
+        None => SearchInput::Key(k),
+    }
+}
+
 pub fn handle_msg(model: &mut Model, m: Message) -> Result<Update> {
     match m {
         Message::SwitchState(state) => {
@@ -170,27 +347,28 @@ pub fn handle_msg(model: &mut Model, m: Message) -> Result<Update> {
             Ok(Update::empty())
         }
         Message::SwitchScreen(to) => {
-            model.screen = match to {
-                Screen::Library => Screen::Library,
-                Screen::Queue => Screen::Queue,
+            let update = match to {
+                Screen::Playlists => Update::PLAYLISTS,
+                _ => Update::empty(),
             };
-            Ok(Update::empty())
+            model.screen = to;
+            Ok(update)
         }
         Message::PlayPause => {
-            model.conn.toggle_pause()?;
+            model.conn.client()?.toggle_pause()?;
             Ok(Update::STATUS)
         }
         Message::NextSong => match model.status.state {
             PlayState::Stop => Ok(Update::empty()),
             _ => {
-                model.conn.next()?;
+                model.conn.client()?.next()?;
                 Ok(Update::CURRENT_SONG | Update::STATUS)
             }
         },
         Message::PreviousSong => match model.status.state {
             PlayState::Stop => Ok(Update::empty()),
             _ => {
-                model.conn.prev()?;
+                model.conn.client()?.prev()?;
                 Ok(Update::CURRENT_SONG | Update::STATUS)
             }
         },
@@ -212,10 +390,10 @@ pub fn handle_msg(model: &mut Model, m: Message) -> Result<Update> {
                 };
 
                 if new_pos >= total {
-                    model.conn.next()?;
+                    model.conn.client()?.next()?;
                     update_flags |= Update::CURRENT_SONG | Update::STATUS;
                 } else {
-                    model.conn.seek(queue_pos.pos, new_pos)?;
+                    model.conn.client()?.seek(queue_pos.pos, new_pos)?;
                     update_flags |= Update::STATUS;
                 }
             }
@@ -223,17 +401,59 @@ pub fn handle_msg(model: &mut Model, m: Message) -> Result<Update> {
         }
         Message::Set(t) => {
             match t {
-                Toggle::Repeat => model.conn.repeat(!model.status.repeat),
-                Toggle::Single => model.conn.single(!model.status.single),
-                Toggle::Random => model.conn.random(!model.status.random),
-                Toggle::Consume => model.conn.consume(!model.status.consume),
+                Toggle::Repeat => {
+                    model.conn.client()?.repeat(!model.status.repeat)
+                }
+                Toggle::Single => {
+                    model.conn.client()?.single(!model.status.single)
+                }
+                Toggle::Random => {
+                    model.conn.client()?.random(!model.status.random)
+                }
+                Toggle::Consume => {
+                    model.conn.client()?.consume(!model.status.consume)
+                }
             }?;
             Ok(Update::STATUS)
         }
         Message::Clear => {
-            model.conn.clear()?;
+            model.conn.client()?.clear()?;
             Ok(Update::STATUS | Update::QUEUE | Update::CURRENT_SONG)
         }
+        Message::SaveQueue => {
+            handlers::prompt_handler::start_prompt(
+                model,
+                "Save queue as",
+                String::new(),
+                PromptAction::SaveQueue,
+                None,
+            );
+            Ok(Update::empty())
+        }
+        Message::ReloadConfig => {
+            model.reload_config();
+            Ok(Update::empty())
+        }
+        Message::SwitchServer => {
+            if model.config.servers.is_empty() {
+                model.message = Some("no [servers] configured".to_string());
+                return Ok(Update::empty());
+            }
+            let names = model
+                .config
+                .servers
+                .iter()
+                .map(|p| p.name.clone())
+                .collect();
+            handlers::prompt_handler::start_prompt(
+                model,
+                "Switch to server",
+                String::new(),
+                PromptAction::SwitchServer,
+                Some(names),
+            );
+            Ok(Update::empty())
+        }
         other => match model.screen {
             Screen::Library => {
                 handlers::library_handler::handle_library(model, other)
@@ -241,6 +461,12 @@ pub fn handle_msg(model: &mut Model, m: Message) -> Result<Update> {
             Screen::Queue => {
                 handlers::queue_handler::handle_queue(model, other)
             }
+            Screen::Playlists => {
+                handlers::playlist_handler::handle_playlists(model, other)
+            }
+            Screen::Directories => {
+                handlers::directory_handler::handle_directories(model, other)
+            }
         },
     }
 }
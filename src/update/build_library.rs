@@ -1,34 +1,188 @@
 extern crate mpd;
+use crate::connection::MpdClient;
 use crate::event_handler::Result;
 use crate::model::proto::*;
-use crate::model::{AlbumData, ArtistData, Model};
+use crate::model::*;
+use crate::util::{song_sort_title, song_tag};
 use itertools::Itertools;
-use mpd::{Query, Term};
+use mpd::{Query, Song, Term};
 use std::borrow::Cow::Borrowed;
+use std::collections::HashMap;
+use std::sync::Arc;
 
+// takes the library from the cache if it is as new as the database. With
+// a loader, an older one is shown until the new one has been fetched.
 pub fn build_library(model: &mut Model) -> Result<()> {
-    let artists = model
-        .conn
+    let db_update = model.conn.client()?.stats()?.db_update.as_secs();
+    let cached = LibraryCache::load(&model.conn.server, &model.conn.url());
+    let fresh = cached.as_ref().is_some_and(|c| c.db_update == db_update);
+    let loader = model.library.loader.as_ref();
+    if let Some(loader) = loader.filter(|_| !fresh) {
+        loader.refresh(model.conn.server.clone());
+    }
+    match cached.filter(|_| fresh || loader.is_some()) {
+        Some(cache) => {
+            model.library.db_update = Some(cache.db_update);
+            model.library.contents = cache.artists;
+            model.library.global_search.contents =
+                Some(Arc::new(cache.entries));
+        }
+        // the artists are quick to list, so they aren't left to the loader
+        None => {
+            model.library.db_update = Some(db_update);
+            model.library.contents = fetch_artists(model.conn.client()?)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn fetch_artists(
+    client: &mut MpdClient,
+) -> mpd::error::Result<Vec<ArtistData>> {
+    let artists = client
         .list_group_2(("albumartistsort".into(), "albumartist".into()))?;
+    // the sort names of the artists of their songs, as extra search keys
+    let mut artist_sorts = client
+        .list_group_2(("artistsort".into(), "albumartist".into()))?
+        .chunk_by(|_a, b| b.0 == "ArtistSort")
+        .filter_map(|chunk| {
+            let (first, rest) = chunk.split_first()?;
+            Some((first.1.clone(), rest.iter().map(|i| i.1.clone()).collect()))
+        })
+        .collect::<HashMap<String, Vec<String>>>();
 
+    let mut contents = Vec::new();
     for chunk in artists.chunk_by(|_a, b| b.0 == "AlbumArtistSort") {
         if let Some(albumartist) = chunk.first().map(|i| i.1.clone()) {
-            model.library.contents.push(ArtistData::from_names(
-                albumartist,
-                chunk.iter().skip(1).map(|i| i.1.clone()).collect(),
-            ));
+            let mut sort_names: Vec<String> =
+                chunk.iter().skip(1).map(|i| i.1.clone()).collect();
+            // only the artist's own sort name orders the list
+            let key = sort_names.first().unwrap_or(&albumartist).to_lowercase();
+            for name in artist_sorts.remove(&albumartist).unwrap_or_default() {
+                if !name.is_empty()
+                    && name != albumartist
+                    && !sort_names.contains(&name)
+                {
+                    sort_names.push(name);
+                }
+            }
+            contents
+                .push((key, ArtistData::from_names(albumartist, sort_names)));
         }
     }
-    model.library.contents.sort_by(|a, b| {
-        let a_name = a.sort_names.first().unwrap_or(&a.name);
-        let b_name = b.sort_names.first().unwrap_or(&a.name);
-        a_name.to_lowercase().cmp(&b_name.to_lowercase())
-    });
+    contents.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(contents.into_iter().map(|(_, a)| a).collect())
+}
+
+pub fn fetch_entries(
+    client: &mut MpdClient,
+) -> mpd::error::Result<Vec<InfoEntry>> {
+    let mut res = client.list_groups(vec![
+        "title",
+        "genre",
+        "date",
+        "album",
+        "albumartistsort",
+        "albumartist",
+    ])?;
+    let mut contents: Vec<InfoEntry> = Vec::new();
+    for vec in res.iter_mut() {
+        let level = vec.len();
+        let ie = InfoEntry::from(vec);
+        match level {
+            // the date and genre levels only qualify the album above. An
+            // album spanning several dates or genres keeps the first of
+            // each, so field terms only match that one.
+            4 | 5 => {
+                if let Some(album) = contents
+                    .last_mut()
+                    .filter(|e| e.album.is_some() && e.title.is_none())
+                {
+                    album.date = album.date.take().or(ie.date);
+                    album.genre = album.genre.take().or(ie.genre);
+                }
+            }
+            _ if ie.is_redundant() => {}
+            _ => contents.push(ie),
+        }
+    }
+    Ok(contents)
+}
+
+// refetches everything derived from the database, keeping the selected
+// artist selected. Its albums and track selection are carried over so that
+// the next `add_tracks` can restore the fold state.
+pub fn rebuild_library(model: &mut Model) -> Result<()> {
+    // the loaded directories are listed again when next shown
+    if model.directories.root.fetched {
+        model.directories.stale = true;
+    }
+    // what is shown stays up until the loader is done
+    if let Some(loader) = &model.library.loader {
+        if !model.library.contents.is_empty() {
+            loader.refresh(model.conn.server.clone());
+            return Ok(());
+        }
+    }
+    let selected = take_selected(model);
+    build_library(model)?;
+    restore_selected(model, selected);
     Ok(())
 }
 
+// swaps in a library fetched by the loader, if it is still for the
+// server connected to. Refreshes can finish out of order, so one older
+// than what is shown is dropped.
+pub fn apply_library(model: &mut Model, cache: LibraryCache) {
+    if cache.server != model.conn.server
+        || model.library.db_update.is_some_and(|d| cache.db_update < d)
+    {
+        return;
+    }
+    let selected = take_selected(model);
+    model.library.db_update = Some(cache.db_update);
+    model.library.contents = cache.artists;
+    model.library.global_search.contents = Some(Arc::new(cache.entries));
+    restore_selected(model, selected);
+    let global = &mut model.library.global_search;
+    if global.search.active {
+        global.update_filter_cache(&mut model.matcher, model.window_height);
+    }
+}
+
+// clears the library, holding on to the selected artist
+fn take_selected(model: &mut Model) -> Option<ArtistData> {
+    let selected = model
+        .library
+        .selected_item()
+        .map(|a| a.name.clone())
+        .and_then(|name| {
+            model.library.contents.iter().position(|a| a.name == name)
+        })
+        .map(|i| model.library.contents.swap_remove(i));
+    model.library.contents.clear();
+    model.library.artist_search.cache = FilterCache::new();
+    model.library.global_search.contents = None;
+    model.library.global_search.search.cache = FilterCache::new();
+    selected
+}
+
+fn restore_selected(model: &mut Model, selected: Option<ArtistData>) {
+    let idx = selected.and_then(|old| {
+        let new = model
+            .library
+            .contents
+            .iter_mut()
+            .find(|a| a.name == old.name)?;
+        new.albums = old.albums;
+        new.track_sel_state = old.track_sel_state;
+        model.library.contents().position(|a| a.name == old.name)
+    });
+    model.library.set_selected(idx);
+}
+
 pub fn add_tracks(model: &mut Model) -> Result<()> {
-    let song_data = model.conn.find(
+    let song_data = model.conn.client()?.find(
         Query::new().and(
             Term::Tag(Borrowed("AlbumArtist")),
             match model.library.selected_item_mut() {
@@ -46,19 +200,22 @@ pub fn add_tracks(model: &mut Model) -> Result<()> {
             == b.tags.iter().find(|t| t.0 == "Album")
     }) {
         if let Some(track) = album.first() {
+            let name = song_tag(track, "Album")
+                .cloned()
+                .unwrap_or("<ALBUM NOT FOUND>".into());
+            let mut tracks = album.to_vec();
+            tracks.sort_by_cached_key(track_order);
             albums.push(AlbumData {
-                name: track
-                    .tags
-                    .iter()
-                    .find(|t| t.0 == "Album")
-                    .cloned()
-                    .map(|i| i.1)
-                    .unwrap_or("<ALBUM NOT FOUND>".into()),
-                tracks: album.to_vec(),
+                sort_name: song_tag(track, "AlbumSort")
+                    .filter(|s| **s != name)
+                    .cloned(),
+                name,
+                tracks,
                 expanded: true,
             });
         }
     }
+    albums.sort_by_cached_key(|a| a.sort_key().to_lowercase());
     if let Some(states) = model
         .library
         .selected_item()
@@ -76,3 +233,75 @@ pub fn add_tracks(model: &mut Model) -> Result<()> {
     }
     Ok(())
 }
+
+// disc and track number first, so that titlesort only orders tracks
+// that aren't numbered
+fn track_order(song: &Song) -> (u32, u32, String) {
+    let number = |tag| {
+        song_tag(song, tag)
+            .and_then(|n| n.split('/').next()?.trim().parse().ok())
+            .unwrap_or(0)
+    };
+    (
+        number("Disc"),
+        number("Track"),
+        song_sort_title(song).to_lowercase(),
+    )
+}
+
+// lists one directory. The crate's lsinfo drops the `directory` lines
+// that mpd sends before the songs, so the subdirectories come from
+// listfiles, and lsinfo only takes song paths.
+pub fn add_directory(model: &mut Model, path: &str) -> Result<()> {
+    let mut dir = DirData::new(path.to_string());
+    for (key, name) in model.conn.client()?.listfiles(path)? {
+        if key == "directory" {
+            dir.dirs.push(DirData::new(if path.is_empty() {
+                name
+            } else {
+                format!("{}/{}", path, name)
+            }));
+        }
+    }
+    dir.dirs.sort_by_key(|d| d.name.to_lowercase());
+
+    let target = Song {
+        file: path.to_string(),
+        ..Default::default()
+    };
+    dir.files = model
+        .conn
+        .client()?
+        .lsinfo(&target)?
+        .into_iter()
+        .filter(|s| !s.file.is_empty())
+        .collect();
+
+    model.directories.insert(dir);
+    Ok(())
+}
+
+// lists every loaded directory again after the database changed, parents
+// first, keeping what is unfolded and selected
+pub fn refresh_directories(model: &mut Model) -> Result<()> {
+    let selected = model
+        .directories
+        .selected_item()
+        .map(|i| i.path().to_string());
+    for path in model.directories.root.fetched_paths() {
+        // gone if its parent no longer lists it
+        if model
+            .directories
+            .root
+            .find_mut(&path)
+            .is_some_and(|d| d.fetched)
+        {
+            add_directory(model, &path)?;
+        }
+    }
+    model.directories.stale = false;
+    if let Some(path) = selected {
+        model.directories.select_path(&path);
+    }
+    Ok(())
+}
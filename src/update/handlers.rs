@@ -1,10 +1,14 @@
 use super::*;
 use crate::model::*;
+use crate::util::char_floor;
 use event::KeyModifiers;
-use nucleo_matcher::Matcher;
 use proto::*;
 
+pub mod directory_handler;
 pub mod library_handler;
+pub mod mouse_handler;
+pub mod playlist_handler;
+pub mod prompt_handler;
 pub mod queue_handler;
 
 pub fn handle_vertical(msg: Vertical, selector: &mut impl Selector) {
@@ -25,85 +29,191 @@ pub fn handle_vertical(msg: Vertical, selector: &mut impl Selector) {
     }
 }
 
-// TODO: Figure out a way to eliminate code duplication here
+// readline-style editing shared by every search box and prompt. `cursor`
+// is a byte offset into `query`.
+pub fn edit_query(query: &mut String, cursor: &mut usize, k: KeyEvent) {
+    let ctrl = k.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = k.modifiers.contains(KeyModifiers::ALT);
+    let at = char_floor(query, *cursor);
+    *cursor = match k.code {
+        KeyCode::Char('a') if ctrl => 0,
+        KeyCode::Char('e') if ctrl => query.len(),
+        KeyCode::Char('f') if ctrl => next_char(query, at),
+        KeyCode::Char('b') if ctrl => prev_char(query, at),
+        KeyCode::Char('f') if alt => word_end(query, at),
+        KeyCode::Char('b') if alt => {
+            word_start(query, at, char::is_alphanumeric)
+        }
+        KeyCode::Char('w') if ctrl => {
+            let start = word_start(query, at, |c| !c.is_whitespace());
+            query.replace_range(start..at, "");
+            start
+        }
+        KeyCode::Char('k') if ctrl => {
+            query.truncate(at);
+            at
+        }
+        KeyCode::Char('u') if ctrl => {
+            query.replace_range(..at, "");
+            0
+        }
+        KeyCode::Char(_) if ctrl || alt => at,
+        KeyCode::Char(c) => {
+            query.insert(at, c);
+            at + c.len_utf8()
+        }
+        KeyCode::Backspace => {
+            let start = prev_char(query, at);
+            query.replace_range(start..at, "");
+            start
+        }
+        KeyCode::Delete => {
+            query.replace_range(at..next_char(query, at), "");
+            at
+        }
+        KeyCode::Left => prev_char(query, at),
+        KeyCode::Right => next_char(query, at),
+        KeyCode::Home => 0,
+        KeyCode::End => query.len(),
+        _ => at,
+    };
+}
+
+// line breaks and other control characters mean nothing in a query
+pub fn paste_query(query: &mut String, cursor: &mut usize, text: &str) {
+    let text: String = text.chars().filter(|c| !c.is_control()).collect();
+    let at = char_floor(query, *cursor);
+    query.insert_str(at, &text);
+    *cursor = at + text.len();
+}
+
+// text input goes straight into the query, keys are left to the caller
+fn take_key(f: &mut Filter, input: SearchInput) -> Option<KeyEvent> {
+    match input {
+        SearchInput::Key(k) => return Some(k),
+        SearchInput::Paste(text) => {
+            paste_query(&mut f.query, &mut f.cursor, &text)
+        }
+        SearchInput::Recall(text) => {
+            f.cursor = text.len();
+            f.query = text;
+        }
+    }
+    None
+}
+
+fn next_char(s: &str, i: usize) -> usize {
+    s[i..].chars().next().map_or(i, |c| i + c.len_utf8())
+}
+
+fn prev_char(s: &str, i: usize) -> usize {
+    s[..i].chars().next_back().map_or(i, |c| i - c.len_utf8())
+}
+
+// the end of the word at or after `i`
+fn word_end(s: &str, i: usize) -> usize {
+    let rest = &s[i..];
+    let start = rest.find(char::is_alphanumeric).unwrap_or(rest.len());
+    let len = rest[start..]
+        .find(|c: char| !c.is_alphanumeric())
+        .unwrap_or(rest.len() - start);
+    i + start + len
+}
+
+// the start of the word before `i`, where words are made of `is_word`
+fn word_start(s: &str, i: usize, is_word: impl Fn(char) -> bool) -> usize {
+    let after = |(j, c): (usize, char)| j + c.len_utf8();
+    let end = s[..i]
+        .char_indices()
+        .rfind(|&(_, c)| is_word(c))
+        .map_or(0, after);
+    s[..end]
+        .char_indices()
+        .rfind(|&(_, c)| !is_word(c))
+        .map_or(0, after)
+}
+
 pub fn handle_search_k_tracksel(
-    artist: &mut ArtistData,
-    k: KeyEvent,
-    matcher: &mut Matcher,
+    s: &mut impl RankedSelector,
+    input: SearchInput,
+    matcher: &mut SearchMatcher,
 ) -> Option<Message> {
+    let Some(k) = take_key(s.search_mut(), input) else {
+        s.update_search(matcher);
+        return None;
+    };
     if k.modifiers.contains(KeyModifiers::CONTROL) {
         match k.code {
-            // TODO: keep track of cursor and implement AEFB
-            KeyCode::Char('u') => artist.search.query.clear(),
             KeyCode::Char('n') => {
-                if let Some(Some(r)) = artist.selected_item().map(|i| i.rank) {
-                    let idx = artist
-                        .contents()
-                        .iter()
-                        .position(|i| i.rank == Some(r + 1));
+                let ranks = s.ranks();
+                if let Some(Some(r)) = s.selected().and_then(|i| ranks.get(i)) {
+                    let idx = ranks.iter().position(|i| *i == Some(r + 1));
                     if idx.is_some() {
-                        artist.set_selected(idx)
+                        s.set_selected(idx)
                     }
                 }
             }
             KeyCode::Char('p') => {
-                if let Some(Some(r)) = artist.selected_item().map(|i| i.rank) {
-                    if r > 0 {
-                        artist.set_selected(
-                            artist
-                                .contents()
-                                .iter()
-                                .position(|i| i.rank == Some(r - 1)),
+                let ranks = s.ranks();
+                if let Some(Some(r)) = s.selected().and_then(|i| ranks.get(i)) {
+                    if *r > 0 {
+                        s.set_selected(
+                            ranks.iter().position(|i| *i == Some(r - 1)),
                         );
                     }
                 }
             }
-            _ => {}
+            _ => {
+                let f = s.search_mut();
+                edit_query(&mut f.query, &mut f.cursor, k)
+            }
         }
     } else {
         match k.code {
-            KeyCode::Char(c) => artist.search.query.push(c),
-            KeyCode::Backspace => {
-                let _ = artist.search.query.pop();
-            }
             KeyCode::Esc => {
                 return Some(Message::LocalSearch(SearchMsg::End));
             }
             KeyCode::Enter => return Some(Message::Select),
-            _ => {}
+            _ => {
+                let f = s.search_mut();
+                edit_query(&mut f.query, &mut f.cursor, k)
+            }
         }
     }
-    artist.update_search(matcher);
+    s.update_search(matcher);
     None
 }
 
 pub fn handle_search_k<T>(
     s: &mut impl Searchable<T>,
-    k: KeyEvent,
-    matcher: &mut Matcher,
+    input: SearchInput,
+    matcher: &mut SearchMatcher,
     top_k: Option<usize>,
 ) -> Option<Message> {
+    let Some(k) = take_key(s.filter_mut(), input) else {
+        s.update_filter_cache(matcher, top_k);
+        s.watch_oob();
+        return None;
+    };
     if k.modifiers.contains(KeyModifiers::CONTROL) {
         match k.code {
-            // TODO: keep track of cursor and implement AEFB
-            KeyCode::Char('u') => s.filter_mut().query.clear(),
             KeyCode::Char('n') => handle_vertical(Vertical::Down, s),
             KeyCode::Char('p') => handle_vertical(Vertical::Up, s),
-            _ => {}
+            _ => {
+                let f = s.filter_mut();
+                edit_query(&mut f.query, &mut f.cursor, k)
+            }
         }
     } else {
         match k.code {
-            KeyCode::Char(c) => {
-                s.filter_mut().query.push(c);
-            }
-            KeyCode::Backspace => {
-                let _ = s.filter_mut().query.pop();
-            }
             KeyCode::Esc => {
                 return Some(Message::LocalSearch(SearchMsg::End));
             }
             KeyCode::Enter => return Some(Message::Select),
-            _ => {}
+            _ => {
+                let f = s.filter_mut();
+                edit_query(&mut f.query, &mut f.cursor, k)
+            }
         }
     }
     s.update_filter_cache(matcher, top_k);
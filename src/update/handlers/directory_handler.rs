@@ -0,0 +1,88 @@
+use super::build_library::add_directory;
+use super::*;
+use crate::event_handler::Result;
+use mpd::Query;
+use mpd::Term;
+
+pub fn handle_directories(model: &mut Model, msg: Message) -> Result<Update> {
+    match msg {
+        Message::ToggleScreen => {
+            model.screen = Screen::Library;
+            Ok(Update::empty())
+        }
+        Message::Direction(Dirs::Vert(d)) => {
+            handle_vertical(d, &mut model.directories);
+            Ok(Update::empty())
+        }
+        Message::Select => {
+            match model.directories.selected_item().map(|i| i.item) {
+                Some(DirItemRef::Dir(d)) => model
+                    .conn
+                    .client()?
+                    .findadd(Query::new().and(Term::Base, d.path.clone()))?,
+                Some(DirItemRef::File(s)) => {
+                    model.conn.client()?.push(s)?;
+                }
+                None => {}
+            }
+            Ok(Update::STATUS
+                | Update::QUEUE
+                | Update::START_PLAYING
+                | Update::CURRENT_SONG)
+        }
+        Message::Fold | Message::Direction(Dirs::Horiz(Horizontal::Right)) => {
+            if let Some(dir) = model.directories.selected_dir_mut() {
+                if !dir.fetched {
+                    let path = dir.path.clone();
+                    add_directory(model, &path)?;
+                }
+                if let Some(dir) = model.directories.selected_dir_mut() {
+                    dir.expanded = !dir.expanded;
+                }
+            } else if let Some(idx) = model.directories.parent_idx() {
+                model.directories.set_selected(Some(idx));
+                if let Some(dir) = model.directories.selected_dir_mut() {
+                    dir.expanded = false;
+                }
+            }
+            Ok(Update::empty())
+        }
+        Message::Direction(Dirs::Horiz(Horizontal::Left)) => {
+            match model.directories.selected_dir_mut() {
+                Some(dir) if dir.expanded => dir.expanded = false,
+                _ => {
+                    if let Some(idx) = model.directories.parent_idx() {
+                        model.directories.set_selected(Some(idx));
+                    }
+                }
+            }
+            Ok(Update::empty())
+        }
+        Message::LocalSearch(SearchMsg::Start) => {
+            model.directories.search.set_on();
+            model.state = State::Searching;
+            Ok(Update::empty())
+        }
+        Message::LocalSearch(SearchMsg::End) => {
+            model.state = State::Running;
+            Ok(Update::empty())
+        }
+        Message::Escape => {
+            model.directories.search.set_off();
+            Ok(Update::empty())
+        }
+        _ => Ok(Update::empty()),
+    }
+}
+
+pub fn handle_search(model: &mut Model, input: SearchInput) -> Result<Update> {
+    if let Some(m) = handle_search_k_tracksel(
+        &mut model.directories,
+        input,
+        &mut model.matcher,
+    ) {
+        handle_msg(model, m)
+    } else {
+        Ok(Update::empty())
+    }
+}
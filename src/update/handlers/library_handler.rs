@@ -1,3 +1,4 @@
+use super::prompt_handler::start_prompt;
 use super::*;
 use crate::event_handler::Result;
 use crate::model::ItemRef::*;
@@ -71,7 +72,7 @@ pub fn handle_library(model: &mut Model, msg: Message) -> Result<Update> {
     }
 }
 
-pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
+pub fn handle_search(model: &mut Model, input: SearchInput) -> Result<Update> {
     match (
         &model.library.active,
         model.library.global_search.search.active,
@@ -79,7 +80,7 @@ pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
         (_, true) => {
             if let Some(m) = handle_search_k(
                 &mut model.library.global_search,
-                k,
+                input,
                 &mut model.matcher,
                 model.window_height,
             ) {
@@ -87,7 +88,7 @@ pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
             } else {
                 if let Some(item) = model.library.global_search.selected_item()
                 {
-                    model.jump_to(item.clone());
+                    model.jump_to(item.clone())?;
                 }
                 Ok(Update::empty())
             }
@@ -95,7 +96,7 @@ pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
         (ArtistSelector, _) => {
             if let Some(m) = handle_search_k(
                 &mut model.library,
-                k,
+                input,
                 &mut model.matcher,
                 model.window_height,
             ) {
@@ -107,7 +108,7 @@ pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
         (TrackSelector, _) => {
             if let Some(artist) = model.library.selected_item_mut() {
                 let msg =
-                    handle_search_k_tracksel(artist, k, &mut model.matcher);
+                    handle_search_k_tracksel(artist, input, &mut model.matcher);
                 if let Some(m) = msg {
                     handle_msg(model, m)
                 } else {
@@ -120,6 +121,25 @@ pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
     }
 }
 
+// global search results streamed back from the worker. Batches can come
+// in every few milliseconds, and jumping may fetch tracks, so the library
+// only follows when the selected match changes.
+pub fn handle_matches(model: &mut Model, m: Matches) -> Result<Update> {
+    let before = model.library.global_search.selected_item().cloned();
+    let used = model.library.global_search.apply_matches(
+        m,
+        &mut model.matcher,
+        model.window_height,
+    );
+    if used {
+        let after = model.library.global_search.selected_item().cloned();
+        if let Some(item) = after.filter(|i| Some(i) != before.as_ref()) {
+            model.jump_to(item)?;
+        }
+    }
+    Ok(Update::empty())
+}
+
 pub fn handle_library_artist(
     model: &mut Model,
     msg: Message,
@@ -138,7 +158,7 @@ pub fn handle_library_artist(
         }
         Message::Select => {
             if let Some(artist) = model.library.selected_item() {
-                model.conn.findadd(Query::new().and(
+                model.conn.client()?.findadd(Query::new().and(
                     Term::Tag(Borrowed("AlbumArtist")),
                     artist.name.clone(),
                 ))?;
@@ -155,7 +175,7 @@ pub fn handle_library_artist(
 pub fn add_item(model: &mut Model) -> Result<Update> {
     if let Some(artist) = model.library.selected_item_mut() {
         match artist.selected_item().map(|i| i.item) {
-            Some(Album(album)) => model.conn.findadd(
+            Some(Album(album)) => model.conn.client()?.findadd(
                 Query::new()
                     .and(
                         Term::Tag(Borrowed("AlbumArtist")),
@@ -165,6 +185,7 @@ pub fn add_item(model: &mut Model) -> Result<Update> {
             )?,
             Some(Song(song)) => model
                 .conn
+                .client()?
                 .findadd(Query::new().and(Term::File, song.file.clone()))?,
             None => {}
         }
@@ -188,6 +209,33 @@ pub fn handle_library_track(model: &mut Model, msg: Message) -> Result<Update> {
             Ok(Update::empty())
         }
         Message::Select => add_item(model),
+        Message::AddToPlaylist => {
+            let songs = match model
+                .library
+                .selected_item()
+                .and_then(|a| a.selected_item())
+                .map(|i| i.item)
+            {
+                Some(Album(album)) => album.tracks.clone(),
+                Some(Song(song)) => vec![song.clone()],
+                None => return Ok(Update::empty()),
+            };
+            let names = model
+                .conn
+                .client()?
+                .playlists()?
+                .into_iter()
+                .map(|p| p.name)
+                .collect();
+            start_prompt(
+                model,
+                "Add to playlist",
+                String::new(),
+                PromptAction::AddToPlaylist(songs),
+                Some(names),
+            );
+            Ok(Update::empty())
+        }
         Message::Fold | Message::Direction(Dirs::Horiz(Horizontal::Right)) => {
             if let Some(art) = model.library.selected_item_mut() {
                 if let Some(album) = art.selected_album_mut() {
@@ -0,0 +1,111 @@
+use super::*;
+use crate::event_handler::Result;
+use crate::model::LibActiveSelector::*;
+use event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+pub fn handle_mouse(model: &mut Model, m: MouseEvent) -> Result<Update> {
+    if model.popup.is_some() {
+        if let MouseEventKind::Down(_) = m.kind {
+            model.popup = None;
+        }
+        return Ok(Update::empty());
+    }
+    if !matches!(model.state, State::Running) {
+        return Ok(Update::empty());
+    }
+    let pos = Position::new(m.column, m.row);
+    match m.kind {
+        MouseEventKind::Down(MouseButton::Left) => click(model, pos),
+        MouseEventKind::ScrollDown => scroll(model, pos, Vertical::Down),
+        MouseEventKind::ScrollUp => scroll(model, pos, Vertical::Up),
+        _ => Ok(Update::empty()),
+    }
+}
+
+fn click(model: &mut Model, pos: Position) -> Result<Update> {
+    let areas = &model.click_areas;
+    match model.screen {
+        Screen::Library if areas.artists.contains(pos) => {
+            let row = row_at(areas.artists, pos, model.library.offset());
+            model.library.active = ArtistSelector;
+            match row.filter(|&r| r < model.library.len()) {
+                Some(r) => {
+                    model.library.set_selected(Some(r));
+                    Ok(Update::CURRENT_ARTIST)
+                }
+                None => Ok(Update::empty()),
+            }
+        }
+        Screen::Library if areas.tracks.contains(pos) => {
+            let area = areas.tracks;
+            model.library.active = TrackSelector;
+            if let Some(artist) = model.library.selected_item_mut() {
+                if let Some(r) = row_at(area, pos, artist.offset())
+                    .filter(|&r| r < artist.len())
+                {
+                    artist.set_selected(Some(r));
+                }
+            }
+            Ok(Update::empty())
+        }
+        Screen::Queue if areas.queue.contains(pos) => {
+            if let Some(r) = row_at(areas.queue, pos, model.queue.offset())
+                .filter(|&r| r < model.queue.len())
+            {
+                model.queue.set_selected(Some(r));
+            }
+            Ok(Update::empty())
+        }
+        Screen::Queue if areas.progress.contains(pos) => {
+            seek_to(model, areas.progress, pos)
+        }
+        _ => Ok(Update::empty()),
+    }
+}
+
+// the wheel moves the selection of the list under the pointer
+fn scroll(model: &mut Model, pos: Position, d: Vertical) -> Result<Update> {
+    let areas = &model.click_areas;
+    match model.screen {
+        Screen::Library if areas.artists.contains(pos) => {
+            handle_vertical(d, &mut model.library);
+            Ok(Update::CURRENT_ARTIST)
+        }
+        Screen::Library if areas.tracks.contains(pos) => {
+            if let Some(artist) = model.library.selected_item_mut() {
+                handle_vertical(d, artist);
+            }
+            Ok(Update::empty())
+        }
+        Screen::Queue if areas.queue.contains(pos) => {
+            handle_vertical(d, &mut model.queue);
+            Ok(Update::empty())
+        }
+        _ => Ok(Update::empty()),
+    }
+}
+
+// clicks inside the gauge seek to that fraction of the song
+fn seek_to(model: &mut Model, area: Rect, pos: Position) -> Result<Update> {
+    let inner = area.inner(ratatui::layout::Margin::new(1, 1));
+    if !inner.contains(pos) || inner.width == 0 {
+        return Ok(Update::empty());
+    }
+    if let (Some(total), Some(song)) =
+        (model.status.duration, model.status.song)
+    {
+        let ratio = f64::from(pos.x - inner.x) / f64::from(inner.width);
+        model.conn.client()?.seek(song.pos, total.mul_f64(ratio))?;
+    }
+    Ok(Update::STATUS)
+}
+
+// rows of a bordered list start one line below its top; clicks on the
+// border select nothing.
+fn row_at(area: Rect, pos: Position, offset: usize) -> Option<usize> {
+    if pos.y <= area.y || pos.y + 1 >= area.bottom() {
+        return None;
+    }
+    Some(offset + usize::from(pos.y - area.y - 1))
+}
@@ -0,0 +1,172 @@
+use super::prompt_handler::start_prompt;
+use super::*;
+use crate::event_handler::Result;
+use crate::model::PlaylistActiveSelector::*;
+
+pub fn handle_playlists(model: &mut Model, msg: Message) -> Result<Update> {
+    match msg {
+        Message::ToggleScreen => {
+            model.screen = Screen::Library;
+            Ok(Update::empty())
+        }
+        Message::LocalSearch(SearchMsg::Start) => {
+            model.playlists.active = PlaylistSelector;
+            model.playlists.search.set_on();
+            model.state = State::Searching;
+            if model.playlists.len() != 0 {
+                model.playlists.set_selected(Some(0));
+            }
+            Ok(Update::empty())
+        }
+        Message::LocalSearch(SearchMsg::End) => {
+            model.state = State::Running;
+            Ok(Update::empty())
+        }
+        Message::Escape => {
+            match model.playlists.active {
+                PlaylistSelector => model.playlists.search.set_off(),
+                TrackSelector => model.playlists.active = PlaylistSelector,
+            }
+            Ok(Update::empty())
+        }
+        Message::TogglePanel => {
+            model.playlists.active = match model.playlists.active {
+                PlaylistSelector => TrackSelector,
+                TrackSelector => PlaylistSelector,
+            };
+            model.playlists.preview.init();
+            Ok(Update::empty())
+        }
+        // these act on the playlist list, not on the tracks of one
+        Message::Playlist(m) => match model.playlists.active {
+            PlaylistSelector => handle_playlist_msg(model, m),
+            TrackSelector => Ok(Update::empty()),
+        },
+        other => match model.playlists.active {
+            PlaylistSelector => handle_playlist_list(model, other),
+            TrackSelector => handle_playlist_tracks(model, other),
+        },
+    }
+}
+
+pub fn handle_search(model: &mut Model, input: SearchInput) -> Result<Update> {
+    if let Some(m) = handle_search_k(
+        &mut model.playlists,
+        input,
+        &mut model.matcher,
+        model.window_height,
+    ) {
+        handle_msg(model, m)
+    } else {
+        Ok(Update::empty())
+    }
+}
+
+pub fn handle_playlist_list(model: &mut Model, msg: Message) -> Result<Update> {
+    match msg {
+        Message::Direction(Dirs::Vert(d)) => {
+            handle_vertical(d, &mut model.playlists);
+            Ok(Update::empty())
+        }
+        Message::Direction(Dirs::Horiz(Horizontal::Right)) => {
+            model.playlists.active = TrackSelector;
+            model.playlists.preview.init();
+            Ok(Update::empty())
+        }
+        Message::Select => handle_playlist_msg(model, PlaylistMsg::Load),
+        Message::Delete => handle_playlist_msg(model, PlaylistMsg::Delete),
+        _ => Ok(Update::empty()),
+    }
+}
+
+pub fn handle_playlist_tracks(
+    model: &mut Model,
+    msg: Message,
+) -> Result<Update> {
+    match msg {
+        Message::Direction(Dirs::Vert(d)) => {
+            handle_vertical(d, &mut model.playlists.preview);
+            Ok(Update::empty())
+        }
+        Message::Direction(Dirs::Horiz(d)) => {
+            let preview = &mut model.playlists.preview;
+            if let (Some(name), Some(p)) = (&preview.name, preview.selected()) {
+                if preview.len() >= 2 {
+                    let to = match d {
+                        Horizontal::Left => safe_increment(p, preview.len()),
+                        Horizontal::Right => safe_decrement(p, preview.len()),
+                    };
+                    model.conn.client()?.pl_shift(name, p as u32, to as u32)?;
+                    preview.set_selected(Some(to));
+                }
+            }
+            Ok(Update::PLAYLISTS)
+        }
+        Message::Delete => {
+            let preview = &mut model.playlists.preview;
+            if let (Some(name), Some(p)) = (&preview.name, preview.selected()) {
+                model.conn.client()?.pl_delete(name, p as u32)?;
+                preview
+                    .set_selected(Some(safe_decrement(p, preview.len() - 1)));
+            }
+            Ok(Update::PLAYLISTS)
+        }
+        Message::Select => {
+            if let Some(song) = model
+                .playlists
+                .preview
+                .selected()
+                .and_then(|i| model.playlists.preview.tracks.get(i))
+            {
+                model.conn.client()?.push(song)?;
+            }
+            Ok(Update::STATUS
+                | Update::QUEUE
+                | Update::START_PLAYING
+                | Update::CURRENT_SONG)
+        }
+        _ => Ok(Update::empty()),
+    }
+}
+
+pub fn handle_playlist_msg(
+    model: &mut Model,
+    msg: PlaylistMsg,
+) -> Result<Update> {
+    let name = match model.playlists.selected_item() {
+        Some(p) => p.name.clone(),
+        None => return Ok(Update::empty()),
+    };
+    match msg {
+        PlaylistMsg::Load | PlaylistMsg::Append => {
+            if msg == PlaylistMsg::Load {
+                model.conn.client()?.clear()?;
+            }
+            model.conn.client()?.load(&name, ..)?;
+            Ok(Update::STATUS
+                | Update::QUEUE
+                | Update::START_PLAYING
+                | Update::CURRENT_SONG)
+        }
+        PlaylistMsg::Rename => {
+            start_prompt(
+                model,
+                "Rename playlist",
+                name.clone(),
+                PromptAction::RenamePlaylist(name),
+                None,
+            );
+            Ok(Update::empty())
+        }
+        PlaylistMsg::Delete => {
+            start_prompt(
+                model,
+                &format!("Delete playlist \"{}\"?", name),
+                String::new(),
+                PromptAction::DeletePlaylist(name),
+                None,
+            );
+            Ok(Update::empty())
+        }
+    }
+}
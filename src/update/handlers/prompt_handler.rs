@@ -0,0 +1,197 @@
+use super::*;
+use crate::event_handler::Result;
+
+pub fn start_prompt(
+    model: &mut Model,
+    title: &str,
+    input: String,
+    action: PromptAction,
+    completions: Option<Vec<String>>,
+) {
+    model.prompt = Some(Prompt {
+        title: title.to_string(),
+        cursor: input.len(),
+        input,
+        action,
+        completions: completions.map(Completions::new),
+    });
+    model.state = State::Prompting;
+}
+
+pub fn handle_prompt(model: &mut Model, k: KeyEvent) -> Result<Update> {
+    if model
+        .prompt
+        .as_ref()
+        .is_some_and(|p| p.action.is_confirmation())
+    {
+        return handle_confirmation(model, k);
+    }
+    match k.code {
+        KeyCode::Esc => {
+            model.prompt = None;
+            model.state = State::Running;
+            Ok(Update::empty())
+        }
+        KeyCode::Enter => {
+            model.state = State::Running;
+            match model.prompt.take() {
+                Some(p) => {
+                    let input = p
+                        .completions
+                        .as_ref()
+                        .and_then(|c| c.selected_item().cloned())
+                        .unwrap_or(p.input);
+                    run_action(model, p.action, input)
+                }
+                None => Ok(Update::empty()),
+            }
+        }
+        _ => {
+            if let Some(p) = &mut model.prompt {
+                match (&mut p.completions, k.code) {
+                    (Some(c), KeyCode::Down) => {
+                        handle_completion_vertical(Vertical::Down, c)
+                    }
+                    (Some(c), KeyCode::Up) => {
+                        handle_completion_vertical(Vertical::Up, c)
+                    }
+                    (Some(c), KeyCode::Char('n'))
+                        if k.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        handle_completion_vertical(Vertical::Down, c)
+                    }
+                    (Some(c), KeyCode::Char('p'))
+                        if k.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        handle_completion_vertical(Vertical::Up, c)
+                    }
+                    _ => {
+                        edit_query(&mut p.input, &mut p.cursor, k);
+                        refresh_completions(p, &mut model.matcher);
+                    }
+                }
+            }
+            Ok(Update::empty())
+        }
+    }
+}
+
+pub fn paste_prompt(model: &mut Model, text: &str) {
+    match &mut model.prompt {
+        Some(p) if !p.action.is_confirmation() => {
+            paste_query(&mut p.input, &mut p.cursor, text);
+            refresh_completions(p, &mut model.matcher);
+        }
+        _ => {}
+    }
+}
+
+// nothing is selected until it is moved onto, so that a new name that
+// happens to match an existing one is still used as typed
+fn refresh_completions(p: &mut Prompt, matcher: &mut SearchMatcher) {
+    if let Some(c) = &mut p.completions {
+        c.search.query = p.input.clone();
+        c.update_filter_cache(matcher, None);
+        c.set_selected(None);
+    }
+}
+
+// moving up from the first completion deselects it, so that the typed
+// input is used as is.
+fn handle_completion_vertical(msg: Vertical, c: &mut Completions) {
+    match (msg, c.selected()) {
+        (Vertical::Up, Some(0)) => c.set_selected(None),
+        (msg, _) => handle_vertical(msg, c),
+    }
+}
+
+fn handle_confirmation(model: &mut Model, k: KeyEvent) -> Result<Update> {
+    match k.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => {
+            model.state = State::Running;
+            match model.prompt.take() {
+                Some(p) => run_action(model, p.action, p.input),
+                None => Ok(Update::empty()),
+            }
+        }
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+            model.prompt = None;
+            model.state = State::Running;
+            Ok(Update::empty())
+        }
+        _ => Ok(Update::empty()),
+    }
+}
+
+fn run_action(
+    model: &mut Model,
+    action: PromptAction,
+    input: String,
+) -> Result<Update> {
+    match action {
+        PromptAction::RenamePlaylist(from) => {
+            if !input.is_empty() && input != from {
+                model.conn.client()?.pl_rename(&from, &input)?;
+            }
+            Ok(Update::PLAYLISTS)
+        }
+        PromptAction::SaveQueue => {
+            if input.is_empty() {
+                return Ok(Update::empty());
+            }
+            if model
+                .conn
+                .client()?
+                .playlists()?
+                .iter()
+                .any(|p| p.name == input)
+            {
+                start_prompt(
+                    model,
+                    &format!("Overwrite playlist \"{}\"?", input),
+                    String::new(),
+                    PromptAction::OverwritePlaylist(input),
+                    None,
+                );
+                return Ok(Update::empty());
+            }
+            model.conn.client()?.save(&input)?;
+            Ok(Update::PLAYLISTS)
+        }
+        // the client library can't send `save <name> replace`, so the
+        // queue is saved under another name first. The old playlist is
+        // only removed once that has worked.
+        PromptAction::OverwritePlaylist(name) => {
+            let tmp = format!("{}.inori-tmp", name);
+            let client = model.conn.client()?;
+            client.save(&tmp)?;
+            if let Err(e) = client.pl_remove(&name) {
+                let _ = client.pl_remove(&tmp);
+                return Err(e.into());
+            }
+            client.pl_rename(&tmp, &name)?;
+            Ok(Update::PLAYLISTS)
+        }
+        PromptAction::DeletePlaylist(name) => {
+            model.conn.client()?.pl_remove(&name)?;
+            Ok(Update::PLAYLISTS)
+        }
+        PromptAction::AddToPlaylist(songs) => {
+            if input.is_empty() {
+                return Ok(Update::empty());
+            }
+            for song in &songs {
+                model.conn.client()?.pl_push(&input, song)?;
+            }
+            Ok(Update::PLAYLISTS)
+        }
+        PromptAction::SwitchServer => {
+            if model.switch_server(&input) {
+                Ok(Update::all())
+            } else {
+                model.message = Some(format!("no server named \"{}\"", input));
+                Ok(Update::empty())
+            }
+        }
+    }
+}
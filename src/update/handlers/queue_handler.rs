@@ -15,6 +15,7 @@ pub fn handle_queue(model: &mut Model, msg: Message) -> Result<Update> {
             if let Some(s) = model.queue.selected_item() {
                 model
                     .conn
+                    .client()?
                     .switch(s.place.expect("Selected song has no place").pos)?;
             }
             Ok(Update::STATUS | Update::CURRENT_SONG)
@@ -30,7 +31,7 @@ pub fn handle_queue(model: &mut Model, msg: Message) -> Result<Update> {
                             safe_decrement(p, model.queue.len())
                         }
                     };
-                    model.conn.swap(p as u32, to as u32)?;
+                    model.conn.client()?.swap(p as u32, to as u32)?;
                     model.queue.set_selected(Some(to));
                     model.queue.watch_oob();
                 }
@@ -39,7 +40,7 @@ pub fn handle_queue(model: &mut Model, msg: Message) -> Result<Update> {
         }
         Message::Delete => {
             if let Some(p) = model.queue.selected() {
-                model.conn.delete(p as u32)?;
+                model.conn.client()?.delete(p as u32)?;
                 model.queue.set_selected(Some(safe_decrement(
                     p,
                     model.queue.len() - 1,
@@ -63,16 +64,17 @@ pub fn handle_queue(model: &mut Model, msg: Message) -> Result<Update> {
         Message::Escape => {
             model.queue.search.active = false;
             model.queue.search.query = String::new();
+            model.queue.search.cursor = 0;
             Ok(Update::empty())
         }
         _ => Ok(Update::empty()),
     }
 }
 
-pub fn handle_search(model: &mut Model, k: KeyEvent) -> Result<Update> {
+pub fn handle_search(model: &mut Model, input: SearchInput) -> Result<Update> {
     if let Some(m) = handle_search_k(
         &mut model.queue,
-        k,
+        input,
         &mut model.matcher,
         model.window_height,
     ) {
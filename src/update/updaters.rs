@@ -28,3 +28,32 @@ pub fn update_queue(model: &mut Model) -> Result<()> {
     }
     Ok(())
 }
+
+pub fn update_playlists(model: &mut Model) -> Result<()> {
+    model.playlists.watch_oob();
+    if model.playlists.len() != 0 && model.playlists.selected().is_none() {
+        model.playlists.set_selected(Some(0));
+    }
+    if model.playlists.preview_is_stale() {
+        let name = model.playlists.selected_item().map(|p| p.name.clone());
+        model.playlists.preview.tracks = match &name {
+            Some(n) => model.conn.client()?.playlist(n).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        model.playlists.preview.name = name;
+        model.playlists.preview.set_selected(None);
+    }
+    model.playlists.preview.watch_oob();
+    Ok(())
+}
+
+pub fn update_directories(model: &mut Model) -> Result<()> {
+    if !model.directories.root.fetched {
+        build_library::add_directory(model, "")?;
+    } else if model.directories.stale {
+        build_library::refresh_directories(model)?;
+    }
+    model.directories.watch_oob();
+    model.directories.init();
+    Ok(())
+}
@@ -19,8 +19,38 @@ pub fn safe_decrement(idx: usize, length: usize) -> usize {
     idx - 1
 }
 
+// the closest char boundary at or before `i`, so that a cursor that
+// outlived its text stays usable
+pub fn char_floor(s: &str, i: usize) -> usize {
+    let mut i = i.min(s.len());
+    while !s.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+pub fn song_tag<'a>(s: &'a Song, tag: &str) -> Option<&'a String> {
+    Some(&s.tags.iter().find(|t| t.0 == tag)?.1)
+}
+
 pub fn song_album(s: &Song) -> Option<&String> {
-    Some(&s.tags.iter().find(|t| t.0 == "Album")?.1)
+    song_tag(s, "Album")
+}
+
+// titlesort, falling back to the title
+pub fn song_sort_title(s: &Song) -> &str {
+    song_tag(s, "TitleSort")
+        .or(s.title.as_ref())
+        .map_or("", |t| t.as_str())
+}
+
+// the title followed by a differing titlesort, for searching
+pub fn song_search_title(s: &Song) -> String {
+    let title = s.title.clone().unwrap_or_default();
+    match song_tag(s, "TitleSort") {
+        Some(sort) if *sort != title => format!("{} [{}]", title, sort),
+        _ => title,
+    }
 }
 
 pub fn format_time(d: Duration) -> String {
@@ -41,6 +71,14 @@ pub fn format_progress(s: &Status) -> String {
         String::new()
     }
 }
+pub fn format_status(state: bool) -> String {
+    if state {
+        "#".to_string()
+    } else {
+        "-".to_string()
+    }
+}
+
 pub fn song_to_str(song: &Song) -> String {
     let mut out = String::new();
     if let Some(title) = &song.title {
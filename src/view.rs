@@ -3,7 +3,9 @@ use ratatui::prelude::*;
 use ratatui::style::Color::*;
 use ratatui::style::Style;
 mod artist_select_renderer;
+pub mod directory_renderer;
 pub mod library_renderer;
+pub mod playlist_renderer;
 pub mod queue_renderer;
 mod search_renderer;
 mod status_renderer;
@@ -25,6 +27,8 @@ pub struct Theme {
     pub slash_span: Style,
     pub search_query_active: Style,
     pub search_query_inactive: Style,
+    pub search_match: Style,
+    pub error: Style,
 }
 impl Theme {
     pub fn new() -> Self {
@@ -43,6 +47,32 @@ impl Theme {
             slash_span: Style::new().fg(LightMagenta),
             search_query_active: Style::new().bg(White).fg(Black),
             search_query_inactive: Style::new().bg(DarkGray).fg(Black),
+            search_match: Style::new().bg(DarkGray),
+            error: Style::new().fg(LightRed).bold(),
+        }
+    }
+    // looks up a style by its name in the config file
+    pub fn style_mut(&mut self, name: &str) -> Option<&mut Style> {
+        match name {
+            "item_highlight_active" => Some(&mut self.item_highlight_active),
+            "item_highlight_inactive" => {
+                Some(&mut self.item_highlight_inactive)
+            }
+            "block_active" => Some(&mut self.block_active),
+            "status_artist" => Some(&mut self.status_artist),
+            "status_album" => Some(&mut self.status_album),
+            "status_title" => Some(&mut self.status_title),
+            "artist_sort" => Some(&mut self.artist_sort),
+            "album" => Some(&mut self.album),
+            "playing" => Some(&mut self.playing),
+            "paused" => Some(&mut self.paused),
+            "stopped" => Some(&mut self.stopped),
+            "slash_span" => Some(&mut self.slash_span),
+            "search_query_active" => Some(&mut self.search_query_active),
+            "search_query_inactive" => Some(&mut self.search_query_inactive),
+            "search_match" => Some(&mut self.search_match),
+            "error" => Some(&mut self.error),
+            _ => None,
         }
     }
 }
@@ -52,8 +82,17 @@ pub fn view(model: &mut Model, frame: &mut Frame) {
     // view function should be pure!
 
     let theme = model.config.theme.clone();
+    model.click_areas = ClickAreas::default();
     match model.screen {
         Screen::Library => library_renderer::render(model, frame, &theme),
         Screen::Queue => queue_renderer::render(model, frame, &theme),
+        Screen::Playlists => playlist_renderer::render(model, frame, &theme),
+        Screen::Directories => directory_renderer::render(model, frame, &theme),
+    }
+    if let Some(prompt) = &mut model.prompt {
+        search_renderer::render_prompt(prompt, frame, &theme);
+    }
+    if let Some(popup) = &model.popup {
+        search_renderer::render_popup(popup, frame, &theme);
     }
 }
@@ -78,6 +78,7 @@ pub fn render_artist_list(
             TrackSelector => theme.item_highlight_inactive,
         });
 
+    model.click_areas.artists = area;
     frame.render_stateful_widget(
         artist_list,
         area,
@@ -0,0 +1,85 @@
+use super::artist_select_renderer::render_str_with_idxs;
+use super::search_renderer::make_search_box;
+use super::status_renderer::render_status;
+use super::Theme;
+use crate::model::*;
+use crate::util::format_time;
+use ratatui::prelude::Constraint::*;
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+
+fn dir_item_to_row<'a>(
+    dirs: &DirectoryState,
+    item: &DirSelItem,
+    theme: &Theme,
+) -> Row<'a> {
+    let idxs = item.rank.and_then(|r| dirs.search.cache.indices.get(r));
+    let name = item.name().to_string();
+    let mut line = vec![Span::from(str::repeat("  ", item.depth))];
+    match item.item {
+        DirItemRef::Dir(d) => {
+            line.push(Span::from(if d.expanded { "▾ " } else { "▸ " }))
+        }
+        DirItemRef::File(_) => line.push(Span::from("  ")),
+    }
+    if let Some(idxs) = idxs {
+        let len = name.chars().count();
+        line.extend(render_str_with_idxs(name, idxs, len, theme));
+    } else {
+        line.push(Span::from(name));
+    }
+    let row = match item.item {
+        DirItemRef::Dir(_) => {
+            Row::new(vec![Line::from(line), Line::from("")]).style(theme.album)
+        }
+        DirItemRef::File(s) => Row::new(vec![
+            Line::from(line),
+            Line::from(s.duration.map(format_time).unwrap_or_default())
+                .right_aligned(),
+        ]),
+    };
+    if idxs.is_some() {
+        row.style(theme.search_match)
+    } else {
+        row
+    }
+}
+
+pub fn render(model: &mut Model, frame: &mut Frame, theme: &Theme) {
+    let layout = Layout::vertical(vec![Max(4), Min(1)]).split(frame.size());
+    let tree_and_search =
+        Layout::vertical(vec![Max(3), Min(1)]).split(layout[1]);
+
+    render_status(model, frame, layout[0], theme);
+
+    let rows = model
+        .directories
+        .contents()
+        .iter()
+        .map(|i| dir_item_to_row(&model.directories, i, theme))
+        .collect::<Vec<Row>>();
+    // unopened directories haven't been listed, so aren't searched
+    let title = match model.directories.search.active {
+        true => "Directories (searching opened ones)",
+        false => "Directories",
+    };
+    let table = Table::new(rows, vec![Min(10), Max(9)])
+        .block(Block::bordered().title(title))
+        .highlight_style(theme.item_highlight_active);
+
+    let area = if model.directories.search.active {
+        frame.render_widget(
+            make_search_box(
+                &model.directories.search.query,
+                model.directories.search.cursor,
+                matches!(model.state, State::Searching),
+                theme,
+            ),
+            tree_and_search[0],
+        );
+        tree_and_search[1]
+    } else {
+        layout[1]
+    };
+    frame.render_stateful_widget(table, area, &mut model.directories.state);
+}
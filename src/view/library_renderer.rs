@@ -68,7 +68,12 @@ pub fn render_global_search(
         area,
     );
     frame.render_widget(
-        make_search_box(&model.library.global_search.search.query, true, theme),
+        make_search_box(
+            &model.library.global_search.search.query,
+            model.library.global_search.search.cursor,
+            true,
+            theme,
+        ),
         layout[0],
     );
     let list = List::new(
@@ -125,6 +130,7 @@ pub fn render(model: &mut Model, frame: &mut Frame, theme: &Theme) {
         frame.render_widget(
             make_search_box(
                 &model.library.selected_item().unwrap().search.query,
+                model.library.selected_item().unwrap().search.cursor,
                 matches!(model.state, State::Searching),
                 theme,
             ),
@@ -139,6 +145,7 @@ pub fn render(model: &mut Model, frame: &mut Frame, theme: &Theme) {
         frame.render_widget(
             make_search_box(
                 &model.library.artist_search.query,
+                model.library.artist_search.cursor,
                 matches!(model.state, State::Searching),
                 theme,
             ),
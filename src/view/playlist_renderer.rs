@@ -0,0 +1,141 @@
+use super::artist_select_renderer::render_str_with_idxs;
+use super::search_renderer::make_search_box;
+use super::status_renderer::render_status;
+use super::Theme;
+use crate::model::proto::*;
+use crate::model::PlaylistActiveSelector::*;
+use crate::model::*;
+use crate::util::{format_time, song_album};
+use ratatui::prelude::Constraint::*;
+use ratatui::prelude::*;
+use ratatui::widgets::*;
+use std::time::Duration;
+
+pub fn get_playlist_list<'a>(model: &Model, theme: &Theme) -> List<'a> {
+    if model.playlists.should_filter() {
+        let indices = &model.playlists.search.cache.indices;
+        List::new(model.playlists.contents().zip(indices).map(
+            |(playlist, idxs)| {
+                Line::from(render_str_with_idxs(
+                    playlist.name.clone(),
+                    idxs,
+                    playlist.name.chars().count(),
+                    theme,
+                ))
+            },
+        ))
+    } else {
+        List::new(
+            model
+                .playlists
+                .contents()
+                .map(|playlist| playlist.name.clone())
+                .collect::<Vec<String>>(),
+        )
+    }
+}
+
+pub fn make_playlist_tracks<'a>(model: &Model, theme: &Theme) -> Table<'a> {
+    let rows: Vec<Row> = model
+        .playlists
+        .preview
+        .tracks
+        .iter()
+        .map(|song| {
+            Row::new(vec![
+                Cell::from(song.title.clone().unwrap_or(song.file.clone())),
+                Cell::from(
+                    Text::from(
+                        song.artist.clone().unwrap_or("Unknown Artist".into()),
+                    )
+                    .style(theme.status_artist),
+                ),
+                Cell::from(
+                    Text::from(
+                        song_album(song)
+                            .cloned()
+                            .unwrap_or("Unknown Album".into()),
+                    )
+                    .style(theme.album),
+                ),
+                Cell::from(
+                    Text::from(format_time(
+                        song.duration.unwrap_or(Duration::new(0, 0)),
+                    ))
+                    .right_aligned(),
+                ),
+            ])
+        })
+        .collect();
+    Table::new(
+        rows,
+        vec![Percentage(50), Percentage(25), Percentage(25), Min(5)],
+    )
+}
+
+pub fn render(model: &mut Model, frame: &mut Frame, theme: &Theme) {
+    let layout = Layout::vertical(vec![Max(4), Min(1)]).split(frame.size());
+    let menu_layout =
+        Layout::horizontal(vec![Ratio(1, 3), Ratio(2, 3)]).split(layout[1]);
+    let left_panel =
+        Layout::vertical(vec![Max(3), Min(1)]).split(menu_layout[0]);
+
+    if model
+        .window_height
+        .is_some_and(|i| i != frame.size().height.into())
+    {
+        model.window_height = Some(frame.size().height.into());
+    }
+
+    render_status(model, frame, layout[0], theme);
+
+    let list = get_playlist_list(model, theme)
+        .block(
+            match model.playlists.active {
+                PlaylistSelector => {
+                    Block::bordered().border_style(theme.block_active)
+                }
+                TrackSelector => Block::bordered(),
+            }
+            .title("Playlists"),
+        )
+        .highlight_style(match model.playlists.active {
+            PlaylistSelector => theme.item_highlight_active,
+            TrackSelector => theme.item_highlight_inactive,
+        });
+    let list_area = if model.playlists.search.active {
+        frame.render_widget(
+            make_search_box(
+                &model.playlists.search.query,
+                model.playlists.search.cursor,
+                matches!(model.state, State::Searching),
+                theme,
+            ),
+            left_panel[0],
+        );
+        left_panel[1]
+    } else {
+        menu_layout[0]
+    };
+    frame.render_stateful_widget(list, list_area, &mut model.playlists.state);
+
+    let tracks = make_playlist_tracks(model, theme)
+        .block(
+            match model.playlists.active {
+                PlaylistSelector => Block::bordered(),
+                TrackSelector => {
+                    Block::bordered().border_style(theme.block_active)
+                }
+            }
+            .title("Tracks"),
+        )
+        .highlight_style(match model.playlists.active {
+            PlaylistSelector => theme.item_highlight_inactive,
+            TrackSelector => theme.item_highlight_active,
+        });
+    frame.render_stateful_widget(
+        tracks,
+        menu_layout[1],
+        &mut model.playlists.preview.state,
+    );
+}
@@ -91,17 +91,20 @@ pub fn render(model: &mut Model, frame: &mut Frame, theme: &Theme) {
         frame.render_widget(
             make_search_box(
                 &model.queue.search.query,
+                model.queue.search.cursor,
                 matches!(model.state, State::Searching),
                 theme,
             ),
             queue_and_search[0],
         );
+        model.click_areas.queue = queue_and_search[1];
         frame.render_stateful_widget(
             table,
             queue_and_search[1],
             &mut model.queue.state,
         );
     } else {
+        model.click_areas.queue = layout[1];
         frame.render_stateful_widget(table, layout[1], &mut model.queue.state);
     }
 
@@ -110,5 +113,6 @@ pub fn render(model: &mut Model, frame: &mut Frame, theme: &Theme) {
         _ => 0 as f64,
     };
 
+    model.click_areas.progress = layout[2];
     frame.render_widget(make_progress_bar(ratio), layout[2])
 }
@@ -1,19 +1,125 @@
+use super::artist_select_renderer::render_str_with_idxs;
 use super::Theme;
+use crate::model::proto::*;
+use crate::model::{Popup, Prompt};
+use crate::util::char_floor;
+use ratatui::prelude::Constraint::*;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 
+// the cursor is drawn as a reversed cell while the box is active
 pub fn make_search_box<'a>(
-    query: &'a String,
+    query: &'a str,
+    cursor: usize,
     active: bool,
     theme: &Theme,
 ) -> Paragraph<'a> {
-    Paragraph::new(vec![Line::from(vec![
-        Span::from("> "),
-        Span::from(query).style(if active {
-            theme.search_query_active
+    let style = if active {
+        theme.search_query_active
+    } else {
+        theme.search_query_inactive
+    };
+    let mut line = vec![Span::from("> ")];
+    if active {
+        let (head, tail) = query.split_at(char_floor(query, cursor));
+        let len = tail.chars().next().map_or(0, char::len_utf8);
+        let under = if len == 0 { " " } else { &tail[..len] };
+        line.push(Span::styled(head, style));
+        line.push(Span::styled(under, style.add_modifier(Modifier::REVERSED)));
+        line.push(Span::styled(&tail[len..], style));
+    } else {
+        line.push(Span::styled(query, style));
+    }
+    Paragraph::new(Line::from(line))
+        .block(Block::bordered().border_type(BorderType::Thick))
+}
+
+pub fn render_prompt(prompt: &mut Prompt, frame: &mut Frame, theme: &Theme) {
+    let popup_h = Layout::horizontal(vec![
+        Percentage(25),
+        Percentage(50),
+        Percentage(25),
+    ])
+    .split(frame.size());
+    let height = match &prompt.completions {
+        Some(c) => 3 + 2 + c.len().clamp(1, 10) as u16,
+        None => 3,
+    };
+    let popup_v = Layout::vertical(vec![Min(0), Length(height), Min(0)])
+        .split(popup_h[1]);
+    let popup = Layout::vertical(vec![Length(3), Min(0)]).split(popup_v[1]);
+
+    frame.render_widget(Clear, popup_v[1]);
+    let body = if prompt.action.is_confirmation() {
+        Paragraph::new("[y/n]")
+    } else {
+        make_search_box(&prompt.input, prompt.cursor, true, theme)
+    };
+    frame.render_widget(
+        body.block(
+            Block::bordered()
+                .border_type(BorderType::Thick)
+                .title(prompt.title.as_str()),
+        ),
+        popup[0],
+    );
+
+    if let Some(c) = &mut prompt.completions {
+        let list = if c.should_filter() {
+            List::new(c.contents().zip(&c.search.cache.indices).map(
+                |(name, idxs)| {
+                    Line::from(render_str_with_idxs(
+                        name.clone(),
+                        idxs,
+                        name.chars().count(),
+                        theme,
+                    ))
+                },
+            ))
         } else {
-            theme.search_query_inactive
-        }),
-    ])])
-    .block(Block::bordered().border_type(BorderType::Thick))
+            List::new(c.contents().cloned().collect::<Vec<String>>())
+        };
+        let title = match c.selected_item() {
+            Some(_) => "",
+            None if prompt.input.is_empty() => "",
+            None => "<enter> to create new",
+        };
+        frame.render_stateful_widget(
+            list.block(Block::bordered().title(title))
+                .highlight_style(theme.item_highlight_active),
+            popup[1],
+            &mut c.state,
+        );
+    }
+}
+
+pub fn render_popup(popup: &Popup, frame: &mut Frame, theme: &Theme) {
+    let popup_h = Layout::horizontal(vec![
+        Percentage(15),
+        Percentage(70),
+        Percentage(15),
+    ])
+    .split(frame.size());
+    let height = popup.lines.len() as u16 + 3;
+    let popup_v = Layout::vertical(vec![Min(0), Length(height), Min(0)])
+        .split(popup_h[1]);
+
+    frame.render_widget(Clear, popup_v[1]);
+    frame.render_widget(
+        Paragraph::new(
+            popup
+                .lines
+                .iter()
+                .map(|l| Line::from(l.as_str()).style(theme.error))
+                .chain([Line::from("press any key to continue").centered()])
+                .collect::<Vec<Line>>(),
+        )
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::bordered()
+                .border_type(BorderType::Thick)
+                .title(popup.title.as_str()),
+        ),
+        popup_v[1],
+    );
 }
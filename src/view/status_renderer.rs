@@ -7,20 +7,16 @@ use ratatui::prelude::*;
 use ratatui::style::Styled;
 use ratatui::widgets::*;
 
-pub fn format_status(state: bool) -> String {
-    if state {
-        "#".to_string()
-    } else {
-        "-".to_string()
-    }
-}
-
 pub fn render_status(
     model: &mut Model,
     frame: &mut Frame,
     area: Rect,
     theme: &Theme,
 ) {
+    if let Some(retry) = model.conn.retry_in() {
+        render_disconnected(model, retry.as_secs() + 1, frame, area, theme);
+        return;
+    }
     let w = Table::new::<Vec<Row>, Vec<Constraint>>(
         vec![
             Row::new(vec![
@@ -28,8 +24,11 @@ pub fn render_status(
                     Play | Pause => format_progress(&model.status),
                     Stop => String::new(),
                 }),
-                Cell::from(
-                    match &model.currentsong {
+                Cell::from(match (&model.message, &model.currentsong) {
+                    (Some(m), _) => {
+                        Line::from(m.as_str()).centered().set_style(theme.error)
+                    }
+                    (None, song) => match song {
                         Some(song) => Line::from(
                             song.title
                                 .clone()
@@ -39,7 +38,7 @@ pub fn render_status(
                     }
                     .centered()
                     .set_style(theme.status_title),
-                ),
+                }),
                 Cell::from("⎡r z s c⎤"),
             ]),
             Row::new(vec![
@@ -80,6 +79,34 @@ pub fn render_status(
         ],
         vec![Max(10), Min(10), Max(10)],
     )
-    .block(Block::bordered().border_type(BorderType::Rounded));
+    .block(status_block(model));
     frame.render_widget(w, area);
 }
+
+fn render_disconnected(
+    model: &Model,
+    retry_secs: u64,
+    frame: &mut Frame,
+    area: Rect,
+    theme: &Theme,
+) {
+    let w = Paragraph::new(vec![
+        Line::from(format!("disconnected from {}", model.conn.url()))
+            .style(theme.status_title),
+        Line::from(match &model.conn.last_error {
+            Some(e) => format!("{}, retrying in {}s", e, retry_secs),
+            None => format!("retrying in {}s", retry_secs),
+        }),
+    ])
+    .centered()
+    .block(status_block(model));
+    frame.render_widget(w, area);
+}
+
+fn status_block(model: &Model) -> Block<'_> {
+    let block = Block::bordered().border_type(BorderType::Rounded);
+    match &model.conn.server.name {
+        Some(name) => block.title(name.as_str()),
+        None => block,
+    }
+}
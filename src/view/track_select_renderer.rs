@@ -3,7 +3,7 @@ use super::Theme;
 use crate::model::proto::*;
 use crate::model::LibActiveSelector::*;
 use crate::model::*;
-use crate::util::format_time;
+use crate::util::{format_time, song_search_title};
 use ratatui::prelude::Constraint::*;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
@@ -21,7 +21,7 @@ fn itemref_to_row<'a>(
             let mut album_line = vec![Span::from(" ")];
             if let Some(idxs) = idxs {
                 album_line.extend(render_str_with_idxs(
-                    a.name.clone(),
+                    a.to_fuzzy_find_str(),
                     idxs,
                     a.name.chars().count(),
                     theme,
@@ -41,7 +41,7 @@ fn itemref_to_row<'a>(
             if let Some(title) = s.title.clone() {
                 if let Some(idxs) = idxs {
                     track_line.extend(render_str_with_idxs(
-                        title.clone(),
+                        song_search_title(s),
                         idxs,
                         title.chars().count(),
                         theme,
@@ -63,7 +63,7 @@ fn itemref_to_row<'a>(
         }
     };
     if idxs.is_some() {
-        row.style(Style::new().bg(Color::DarkGray))
+        row.style(theme.search_match)
     } else {
         row
     }
@@ -82,7 +82,7 @@ fn get_track_data<'a>(
             .collect::<Vec<Row>>();
         Table::new::<Vec<Row>, Vec<Constraint>>(items, vec![Min(10), Max(9)])
     } else {
-        return Table::new::<Vec<Row>, Vec<u16>>(vec![], vec![]);
+        Table::new::<Vec<Row>, Vec<u16>>(vec![], vec![])
     }
 }
 
@@ -108,6 +108,7 @@ pub fn render_track_list(
         })
         .highlight_spacing(HighlightSpacing::Always);
 
+    model.click_areas.tracks = area;
     match model.library.selected_item_mut() {
         Some(artist) => frame.render_stateful_widget(
             list,